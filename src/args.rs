@@ -1,6 +1,10 @@
+use std::path::PathBuf;
+
 use clap::{
     ColorChoice,
     Parser,
+    Subcommand,
+    ValueEnum,
 };
 
 #[derive(Parser, Debug)]
@@ -16,4 +20,96 @@ pub struct Args {
     /// Draft commit
     #[arg(short, long)]
     pub message: Option<String>,
+
+    /// Number of suggestions to generate, overrides 'count' in GITGEN.md
+    #[arg(short = 'n', long, value_parser = clap::value_parser!(u8).range(1..))]
+    pub count: Option<u8>,
+
+    /// Print the prompt that would be sent to the provider and exit
+    #[arg(long)]
+    pub print_prompt: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// Numbered list, or the interactive picker in a terminal
+    Text,
+    /// A single JSON object, errors included
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Check GITGEN.md for problems and report all of them
+    Check,
+    /// Create a starter GITGEN.md at the repository root
+    Init {
+        /// Provider to configure: anthropic, command, gemini, grok, ollama,
+        /// openai or openai-compatible
+        #[arg(short, long, default_value = "gemini")]
+        provider: String,
+        /// Infer the style guide from the commit history instead of using the
+        /// Conventional Commits template
+        #[arg(long)]
+        from_history: bool,
+        /// Overwrite an existing GITGEN.md
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Check commit messages against the rules in GITGEN.md, as a commit-msg
+    /// hook or over a revision range in CI
+    Lint {
+        /// Commit message file, like the one given to a commit-msg hook
+        #[arg(required_unless_present = "range")]
+        file: Option<PathBuf>,
+        /// Revision range to check instead, e.g. 'origin/main..HEAD'
+        #[arg(long, conflicts_with = "file")]
+        range: Option<String>,
+    },
+    /// Inspect the prompt sent to the provider
+    Prompt {
+        #[command(subcommand)]
+        command: PromptCommand,
+    },
+    /// Manage the prepare-commit-msg hook
+    Hook {
+        #[command(subcommand)]
+        command: HookCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PromptCommand {
+    /// Print the system instructions in effect, with variables filled in
+    Show,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HookCommand {
+    /// Install a prepare-commit-msg hook that fills in the commit message
+    Install {
+        /// Overwrite an existing prepare-commit-msg hook
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Fill in the commit message file, called by the installed hook
+    Run {
+        /// Path to the commit message file
+        file: PathBuf,
+        /// Where the message comes from: message, template, merge, squash
+        /// or commit
+        source: Option<String>,
+        /// Commit object name, given for the 'commit' source
+        sha: Option<String>,
+        /// Number of suggestions, the first one fills in the message
+        #[arg(short = 'n', long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..))]
+        count: u8,
+    },
 }
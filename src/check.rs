@@ -0,0 +1,312 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::ops::Range;
+
+use serde::Deserialize;
+use toml::Spanned;
+use toml::de::{
+    DeTable,
+    DeValue,
+    ValueDeserializer,
+};
+
+use crate::config::{
+    self,
+    Config,
+};
+use crate::error::{
+    Error,
+    Result,
+};
+use crate::manifest::{
+    self,
+    Sections,
+};
+use crate::{
+    bail,
+    error,
+    git,
+    providers,
+};
+
+/// A problem found in the manifest, located by byte offset
+#[derive(Debug, PartialEq)]
+struct Problem {
+    offset: usize,
+    message: String,
+}
+
+/// Validates the GITGEN.md at the repository root and reports every problem
+/// at once.
+pub fn run() -> Result<()> {
+    let path = git::root()?.join(manifest::FILE_NAME);
+    if !path.exists() {
+        bail!(
+            "manifest file '{}' not found at repository root", manifest::FILE_NAME,
+            help: "run 'git gen init' to create one"
+        );
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| {
+        error!(
+            "failed to read manifest file at '{}'", path.display(),
+            source: e,
+            help: "please check file's permissions"
+        )
+    })?;
+    let user = config::load_user()?.unwrap_or_default();
+
+    let problems = check(&content, &user);
+    if problems.is_empty() {
+        println!("'{}' looks good", path.display());
+        return Ok(());
+    }
+
+    let mut error = Error::new(format!(
+        "found {} problem(s) in '{}'",
+        problems.len(),
+        path.display()
+    ));
+    for problem in &problems {
+        let (line, column) = locate(&content, problem.offset);
+        error = error.note(format!(
+            "{}:{}:{}: {}",
+            manifest::FILE_NAME,
+            line,
+            column,
+            problem.message
+        ));
+    }
+    Err(error)
+}
+
+/// Finds every problem in a GITGEN.md, keys missing from its frontmatter
+/// are looked up in the `user` config.
+fn check(content: &str, user: &toml::Table) -> Vec<Problem> {
+    let sections = match manifest::split(content) {
+        Ok(sections) => sections,
+        Err(e) => {
+            return vec![Problem {
+                offset: 0,
+                message: e.plain(),
+            }];
+        }
+    };
+
+    let mut problems = Vec::new();
+    check_prompt(&sections, &mut problems);
+
+    let (table, errors) = DeTable::parse_recoverable(sections.frontmatter);
+    let offset = sections.frontmatter_start;
+    for e in errors {
+        problems.push(Problem {
+            offset: offset + e.span().map_or(0, |s| s.start),
+            message: e.message().trim().to_string(),
+        });
+    }
+
+    let mut provider_span = None;
+    for (key, value) in table.get_ref() {
+        if key.get_ref() == "provider" {
+            provider_span = Some(key.span());
+        }
+        if let Err(e) = check_key(key, value) {
+            let span = e.span().filter(|s| !s.is_empty()).unwrap_or(key.span());
+            problems.push(Problem {
+                offset: offset + span.start,
+                message: e.message().trim().to_string(),
+            });
+        }
+    }
+
+    if provider_span.is_none() && !user.contains_key("provider") {
+        let frontmatter = sections.frontmatter;
+        problems.push(Problem {
+            offset: offset + frontmatter.len() - frontmatter.trim_start().len(),
+            message: "missing 'provider'".to_string(),
+        });
+    }
+
+    // Provider specific checks only make sense once every key is valid
+    if !problems.is_empty() {
+        problems.sort_by_key(|p| p.offset);
+        return problems;
+    }
+    let Ok(frontmatter) = toml::from_str::<toml::Table>(sections.frontmatter)
+    else {
+        return problems;
+    };
+    let mut merged = user.clone();
+    merged.remove("prompt");
+    merged.extend(frontmatter);
+    let Ok(config) = toml::Value::Table(merged).try_into::<Config>() else {
+        return problems;
+    };
+    let offset = offset + provider_span.map_or(0, |s| s.start);
+
+    if let Err(e) = providers::create(&config) {
+        problems.push(Problem {
+            offset,
+            message: e.plain(),
+        });
+    }
+    if let Some(name) = providers::api_key_env(&config)
+        && env::var_os(&name).is_none()
+    {
+        problems.push(Problem {
+            offset,
+            message: format!("environment variable {} is not set", name),
+        });
+    }
+
+    problems
+}
+
+fn check_prompt(sections: &Sections, problems: &mut Vec<Problem>) {
+    if sections.prompt.trim().is_empty() {
+        problems.push(Problem {
+            offset: sections.prompt_start,
+            message: "the prompt after the frontmatter is empty".to_string(),
+        });
+    }
+}
+
+/// Deserializes a single key into [`Config`], so one bad value doesn't hide
+/// the others.
+fn check_key(
+    key: &Spanned<Cow<str>>,
+    value: &Spanned<DeValue>,
+) -> std::result::Result<(), toml::de::Error> {
+    let mut table = DeTable::new();
+    table.insert(key.clone(), value.clone());
+    // `provider` is required, give the struct a placeholder when checking
+    // other keys
+    if key.get_ref() != "provider" {
+        table.insert(
+            Spanned::new(0..0, Cow::Borrowed("provider")),
+            Spanned::new(0..0, DeValue::String(Cow::Borrowed("command"))),
+        );
+    }
+
+    let span: Range<usize> = value.span();
+    let deserializer = ValueDeserializer::from(Spanned::new(
+        key.span().start..span.end,
+        DeValue::Table(table),
+    ));
+    Config::deserialize(deserializer).map(|_| ())
+}
+
+/// Converts a byte offset into a 1-based line and column
+fn locate(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column =
+        before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(content: &str) -> Vec<(usize, usize, String)> {
+        check(content, &toml::Table::new())
+            .into_iter()
+            .map(|p| {
+                let (line, column) = locate(content, p.offset);
+                (line, column, p.message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let content = r#"---
+provider = "ollama"
+model = "llama3.2"
+---
+some prompt
+"#;
+        assert_eq!(problems(content), vec![]);
+    }
+
+    #[test]
+    fn test_reports_everything() {
+        let content = r#"---
+provider = "gemeni"
+modle = "gemini-2.5-flash"
+max_diff_bytes = "big"
+---
+
+"#;
+        let problems = problems(content);
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert_eq!((problems[0].0, problems[0].1), (2, 12));
+        assert!(problems[0].2.contains("unknown variant `gemeni`"));
+        assert_eq!((problems[1].0, problems[1].1), (3, 1));
+        assert!(problems[1].2.contains("unknown field `modle`"));
+        assert_eq!((problems[2].0, problems[2].1), (4, 18));
+        assert!(problems[2].2.contains("invalid type"));
+        assert_eq!(problems[3].0, 6);
+        assert!(
+            problems[3]
+                .2
+                .contains("prompt after the frontmatter is empty")
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let content = "---\nprovider = \"ollama\"\nmodel = \n---\nprompt\n";
+        let problems = problems(content);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert_eq!(problems[0].0, 3);
+    }
+
+    #[test]
+    fn test_missing_provider() {
+        let content = "---\nmodel = \"x\"\n---\nprompt\n";
+        let problems = problems(content);
+        assert_eq!(problems, vec![(2, 1, "missing 'provider'".to_string())]);
+    }
+
+    #[test]
+    fn test_provider_requirements() {
+        let content = "---\nprovider = \"openai-compatible\"\n---\nprompt\n";
+        let problems = problems(content);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert_eq!((problems[0].0, problems[0].1), (2, 1));
+        assert!(problems[0].2.contains("requires a 'base_url'"));
+    }
+
+    #[test]
+    fn test_missing_api_key() {
+        let content = r#"---
+provider = "openai-compatible"
+base_url = "http://localhost:8000/v1"
+model = "llama3"
+api_key_env = "GIT_GEN_TEST_UNSET_KEY"
+---
+prompt
+"#;
+        let problems = problems(content);
+        assert_eq!(
+            problems,
+            vec![(
+                2,
+                1,
+                "environment variable GIT_GEN_TEST_UNSET_KEY is not set"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_user_config_provides_provider() {
+        let user: toml::Table =
+            toml::from_str("provider = \"ollama\"").unwrap();
+        let content = "---\nmodel = \"x\"\n---\nprompt\n";
+        assert_eq!(check(content, &user), vec![]);
+    }
+}
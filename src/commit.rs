@@ -0,0 +1,217 @@
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// `type(scope)!: subject`
+static HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([A-Za-z][\w-]*)(?:\(([^)]*)\))?(!)?: (.*)$")
+        .expect("header pattern should be valid")
+});
+
+/// A commit message split into its Conventional Commits parts.
+///
+/// Messages that don't follow Conventional Commits have no `kind` and keep
+/// their whole first line in `subject`. [`CommitMessage::parse`] and the
+/// [`fmt::Display`] impl round-trip.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommitMessage {
+    /// The `type` of the header, like `feat`
+    pub kind: Option<String>,
+    pub scope: Option<String>,
+    /// The `!` before the colon in the header
+    pub breaking: bool,
+    /// The header without its `type(scope): ` prefix
+    pub subject: String,
+    pub body: Option<String>,
+    /// Trailer lines such as `BREAKING CHANGE: ...` or `Signed-off-by: ...`,
+    /// continuation lines included
+    pub footers: Vec<String>,
+}
+
+impl CommitMessage {
+    /// Splits a commit message into its parts
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let (header, rest) = text.split_once('\n').unwrap_or((text, ""));
+        let mut message = Self::from_header(header.trim_end());
+
+        // Keep the indentation of the first body line, it may be code
+        let mut paragraphs: Vec<&str> = rest
+            .trim_start_matches(['\r', '\n'])
+            .trim_end()
+            .split("\n\n")
+            .filter(|p| !p.trim().is_empty())
+            .collect();
+        if let Some(last) = paragraphs.last()
+            && let Some(footers) = footers(last)
+        {
+            message.footers = footers;
+            paragraphs.pop();
+        }
+        if !paragraphs.is_empty() {
+            message.body = Some(paragraphs.join("\n\n"));
+        }
+        message
+    }
+
+    /// Parses the first line alone
+    pub fn from_header(header: &str) -> Self {
+        match HEADER.captures(header) {
+            Some(caps) => Self {
+                kind: Some(caps[1].to_string()),
+                scope: caps.get(2).map(|m| m.as_str().to_string()),
+                breaking: caps.get(3).is_some(),
+                subject: caps[4].to_string(),
+                ..Self::default()
+            },
+            None => Self {
+                subject: header.to_string(),
+                ..Self::default()
+            },
+        }
+    }
+
+    /// The first line, `type(scope)!: subject`
+    pub fn header(&self) -> String {
+        let Some(kind) = &self.kind else {
+            return self.subject.clone();
+        };
+        let scope = match &self.scope {
+            Some(scope) => format!("({})", scope),
+            None => String::new(),
+        };
+        let breaking = if self.breaking { "!" } else { "" };
+        format!("{}{}{}: {}", kind, scope, breaking, self.subject)
+    }
+
+    /// Whether the header or a footer marks a breaking change
+    pub fn is_breaking(&self) -> bool {
+        self.breaking
+            || self.footers.iter().any(|f| {
+                f.starts_with("BREAKING CHANGE:")
+                    || f.starts_with("BREAKING-CHANGE:")
+            })
+    }
+}
+
+impl fmt::Display for CommitMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())?;
+        if let Some(body) = &self.body {
+            write!(f, "\n\n{}", body)?;
+        }
+        if !self.footers.is_empty() {
+            write!(f, "\n\n{}", self.footers.join("\n"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `line` is a git trailer like `Signed-off-by: Name <email>`
+pub fn is_trailer(line: &str) -> bool {
+    if line.starts_with("BREAKING CHANGE: ") {
+        return true;
+    }
+    // `Token: value` or `Token #value`
+    let token = line
+        .split_once(": ")
+        .or_else(|| line.split_once(" #"))
+        .map(|(token, _)| token);
+    token.is_some_and(|token| {
+        !token.is_empty()
+            && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+/// Splits `paragraph` into trailers, if it only holds trailers and their
+/// indented continuation lines
+fn footers(paragraph: &str) -> Option<Vec<String>> {
+    let mut footers: Vec<String> = Vec::new();
+    for line in paragraph.lines() {
+        match footers.last_mut() {
+            Some(footer) if line.starts_with([' ', '\t']) => {
+                footer.push('\n');
+                footer.push_str(line);
+            }
+            _ if is_trailer(line) => footers.push(line.to_string()),
+            _ => return None,
+        }
+    }
+    Some(footers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conventional() {
+        let message = CommitMessage::parse(
+            "feat(lint)!: add rules\n\nCheck the subject.\n\nSecond paragraph.\n\n\
+             BREAKING CHANGE: 'max_subject' is now enforced\nRefs: #12\n",
+        );
+        assert_eq!(message.kind.as_deref(), Some("feat"));
+        assert_eq!(message.scope.as_deref(), Some("lint"));
+        assert!(message.breaking);
+        assert_eq!(message.subject, "add rules");
+        assert_eq!(
+            message.body.as_deref(),
+            Some("Check the subject.\n\nSecond paragraph.")
+        );
+        assert_eq!(
+            message.footers,
+            vec![
+                "BREAKING CHANGE: 'max_subject' is now enforced",
+                "Refs: #12"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_plain() {
+        let message = CommitMessage::parse("Fix the build on Windows");
+        assert_eq!(message.kind, None);
+        assert_eq!(message.subject, "Fix the build on Windows");
+        assert_eq!(message.body, None);
+        assert!(message.footers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_body_is_not_footers() {
+        let message =
+            CommitMessage::parse("fix: wrap\n\nThe body: not a trailer at all");
+        assert_eq!(
+            message.body.as_deref(),
+            Some("The body: not a trailer at all")
+        );
+        assert!(message.footers.is_empty());
+    }
+
+    #[test]
+    fn test_footer_continuation() {
+        let message = CommitMessage::parse(
+            "fix: wrap\n\nBREAKING CHANGE: a long\n  explanation\nFixes #3",
+        );
+        assert_eq!(
+            message.footers,
+            vec!["BREAKING CHANGE: a long\n  explanation", "Fixes #3"]
+        );
+        assert!(message.is_breaking());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let texts = [
+            "feat: add a",
+            "feat(parser)!: drop the old syntax\n\nIt was ambiguous.",
+            "Update docs\n\nbody\n\nSigned-off-by: A <a@b.c>",
+            "fix(hook): skip merges\n\n- one\n- two\n\nRefs: #1\nFixes #2",
+        ];
+        for text in texts {
+            let message = CommitMessage::parse(text);
+            assert_eq!(message.to_string(), text);
+            assert_eq!(CommitMessage::parse(&message.to_string()), message);
+        }
+    }
+}
@@ -1,15 +1,152 @@
-use serde::Deserialize;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::{
+    env,
+    fs,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use toml::Table;
+
+use crate::error;
+use crate::error::Result;
 
 #[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub provider: Provider,
     pub model: Option<String>,
+    /// API root for the `openai-compatible` provider, e.g.
+    /// `http://localhost:8000/v1`
+    pub base_url: Option<String>,
+    /// Environment variable holding the API key, overrides the provider's
+    /// default such as `GEMINI_API_KEY`
+    pub api_key_env: Option<String>,
+    /// Address of the Ollama daemon, defaults to `http://localhost:11434`
+    pub host: Option<String>,
+    /// Program run by the `command` provider, it gets the prompt on stdin
+    pub command: Option<String>,
+    /// Arguments passed to `command`
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Size limit in bytes for the staged diff sent to the model, roughly
+    /// four bytes per token, defaults to [`DEFAULT_MAX_DIFF_BYTES`]
+    pub max_diff_bytes: Option<usize>,
+    /// Globs of files to leave out of the diff, replaces [`DEFAULT_EXCLUDE`]
+    pub exclude: Option<Vec<String>>,
+    /// Extra regular expressions for secrets to redact from the diff
+    #[serde(default)]
+    pub redact: Vec<String>,
+    /// File replacing the built-in system instructions, relative to the
+    /// repository root
+    pub instructions: Option<String>,
+    /// File appended to the system instructions, relative to the repository
+    /// root
+    pub extra_instructions: Option<String>,
+    /// Number of suggestions to ask for, defaults to [`DEFAULT_COUNT`]
+    pub count: Option<usize>,
+    /// Longest subject line allowed, in characters
+    pub max_subject: Option<usize>,
+    /// Column the body is wrapped at
+    pub wrap_body: Option<usize>,
+    /// Conventional Commits types the subject may start with
+    pub allowed_types: Option<Vec<String>>,
+    /// Whether the subject must (`true`) or must not (`false`) end with a
+    /// period
+    pub subject_period: Option<bool>,
+    /// Whether the subject needs a `(scope)` after its type
+    #[serde(default)]
+    pub require_scope: bool,
 }
 
-#[derive(Deserialize, Debug)]
+/// Number of suggestions asked for when neither the CLI nor the manifest
+/// says otherwise
+pub const DEFAULT_COUNT: usize = 5;
+
+/// Size limit for the staged diff when the manifest doesn't set one, about
+/// 16k tokens
+pub const DEFAULT_MAX_DIFF_BYTES: usize = 64_000;
+
+/// Lockfiles, minified and vendored code: big diffs that say little about the
+/// change
+pub const DEFAULT_EXCLUDE: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "go.sum",
+    "*.min.js",
+    "*.min.css",
+    "vendor/**",
+];
+
+impl Config {
+    /// Globs of files to leave out of the diff
+    pub fn exclude(&self) -> Vec<String> {
+        match &self.exclude {
+            Some(exclude) => exclude.clone(),
+            None => DEFAULT_EXCLUDE.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum Provider {
+    Anthropic,
+    Command,
     Gemini,
     Grok,
+    Ollama,
     OpenAI,
+    #[serde(rename = "openai-compatible")]
+    OpenAICompatible,
+}
+
+/// Location of the user-level config, `$XDG_CONFIG_HOME/git-gen/config.toml`
+/// or `~/.config/git-gen/config.toml`
+pub fn user_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| Path::new(&home).join(".config"))
+        })?;
+    Some(base.join("git-gen").join("config.toml"))
+}
+
+/// Reads the user-level config as a raw table, if there is one.
+///
+/// It takes the same keys as the GITGEN.md frontmatter plus `prompt`, used
+/// when the repository has no GITGEN.md.
+pub fn load_user() -> Result<Option<Table>> {
+    let Some(path) = user_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| {
+        error!(
+            "failed to read user config at '{}'", path.display(),
+            source: e,
+            help: "please check file's permissions"
+        )
+    })?;
+
+    let table = toml::from_str(&content).map_err(|e| {
+        error!(
+            "failed to parse user config at '{}'", path.display(),
+            source: e,
+            help: "please check for syntax errors in your user config"
+        )
+    })?;
+    Ok(Some(table))
 }
@@ -1,14 +1,39 @@
-use crate::error::Result;
+use crate::config::{
+    self,
+    Config,
+};
+use crate::diff;
+use crate::error::{
+    Result,
+    green,
+};
 use crate::git;
+use crate::redact::Redactor;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Context {
     pub git_diff: String,
     pub git_log: String,
 }
 
-pub fn create() -> Result<Context> {
+pub fn create(config: &Config) -> Result<Context> {
     let git_diff = git::diff()?;
+    let git_diff = diff::exclude(&git_diff, &config.exclude());
+
+    let redactor = Redactor::new(&config.redact)?;
+    let (git_diff, redacted) = redactor.redact(&git_diff);
+    if !redacted.is_empty() {
+        eprintln!(
+            "{}: redacted possible secrets in {}",
+            green("note"),
+            redacted.join(", ")
+        );
+    }
+
+    let budget = config
+        .max_diff_bytes
+        .unwrap_or(config::DEFAULT_MAX_DIFF_BYTES);
+    let git_diff = diff::truncate(&git_diff, budget);
     let git_log = git::previous_commits()?;
     Ok(Context { git_diff, git_log })
 }
@@ -0,0 +1,461 @@
+use crate::glob;
+
+/// One file in a unified diff as produced by `git diff`
+#[derive(Debug, PartialEq)]
+pub struct FileDiff {
+    pub path: String,
+    /// `diff --git`, `index`, `---`, `+++` and similar lines
+    pub header: String,
+    pub hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@` line
+    pub header: String,
+    pub body: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl FileDiff {
+    pub fn added(&self) -> usize {
+        self.hunks.iter().map(|h| h.added).sum()
+    }
+
+    pub fn removed(&self) -> usize {
+        self.hunks.iter().map(|h| h.removed).sum()
+    }
+
+    /// The file exactly as it appeared in the diff
+    pub fn full(&self) -> String {
+        let mut text = self.header.clone();
+        for hunk in &self.hunks {
+            text.push_str(&hunk.header);
+            text.push_str(&hunk.body);
+        }
+        text
+    }
+
+    /// The file with every hunk body replaced by a line count
+    pub fn summary(&self) -> String {
+        let mut text = self.header.clone();
+        for hunk in &self.hunks {
+            text.push_str(&hunk.header);
+            text.push_str(&format!(
+                "[git-gen: hunk elided, +{} -{} lines]\n",
+                hunk.added, hunk.removed
+            ));
+        }
+        text
+    }
+
+    /// The file as a single line with its line counts
+    pub fn name(&self) -> String {
+        format!(
+            "[git-gen: {} changed, +{} -{} lines]\n",
+            self.path,
+            self.added(),
+            self.removed()
+        )
+    }
+}
+
+/// Excluded files listed by name, the rest are only counted
+const MAX_EXCLUDED_NAMES: usize = 20;
+
+/// How much of a file [`truncate`] keeps
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Keep {
+    Name,
+    Summary,
+    Full,
+}
+
+/// Splits the output of `git diff` into files and hunks.
+pub fn parse(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+
+    for line in diff.split_inclusive('\n') {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            files.push(FileDiff {
+                path: path(paths.trim_end()),
+                header: line.to_string(),
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+
+        // Anything before the first file header is not part of a file
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                body: String::new(),
+                added: 0,
+                removed: 0,
+            });
+            continue;
+        }
+
+        match file.hunks.last_mut() {
+            Some(hunk) => {
+                if line.starts_with('+') {
+                    hunk.added += 1;
+                } else if line.starts_with('-') {
+                    hunk.removed += 1;
+                }
+                hunk.body.push_str(line);
+            }
+            None => file.header.push_str(line),
+        }
+    }
+
+    files
+}
+
+/// The `a/` path of a `diff --git` line. Git quotes paths with special
+/// characters C-style, like `"a/caf\303\251.txt"`.
+fn path(paths: &str) -> String {
+    if let Some(quoted) = paths.strip_prefix('"') {
+        let path = unquote(quoted);
+        return path.strip_prefix("a/").unwrap_or(&path).to_string();
+    }
+    let path = paths.strip_prefix("a/").unwrap_or(paths);
+    path.split_once(" b/")
+        .or_else(|| path.split_once(" \"b/"))
+        .map_or(path, |(a, _)| a)
+        .to_string()
+}
+
+/// Reads a C-style quoted string up to its closing quote, octal escapes are
+/// UTF-8 bytes
+fn unquote(text: &str) -> String {
+    let mut bytes = text.bytes();
+    let mut path = Vec::new();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'"' => break,
+            b'\\' => match bytes.next() {
+                Some(high @ b'0'..=b'3') => {
+                    let digits = [Some(high), bytes.next(), bytes.next()];
+                    let value = digits.iter().fold(0u8, |value, digit| {
+                        value * 8 + digit.unwrap_or(b'0').wrapping_sub(b'0')
+                    });
+                    path.push(value);
+                }
+                Some(b'a') => path.push(0x07),
+                Some(b'b') => path.push(0x08),
+                Some(b'f') => path.push(0x0c),
+                Some(b'n') => path.push(b'\n'),
+                Some(b'r') => path.push(b'\r'),
+                Some(b't') => path.push(b'\t'),
+                Some(b'v') => path.push(0x0b),
+                Some(other) => path.push(other),
+                None => break,
+            },
+            _ => path.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&path).into_owned()
+}
+
+/// Drops the files matching any of the glob `patterns` from `diff`.
+///
+/// Excluded files are still listed by name with their line counts, so the
+/// model knows they changed. Past [`MAX_EXCLUDED_NAMES`] only their number
+/// and total line counts are given.
+pub fn exclude(diff: &str, patterns: &[String]) -> String {
+    let (excluded, kept): (Vec<FileDiff>, Vec<FileDiff>) = parse(diff)
+        .into_iter()
+        .partition(|f| patterns.iter().any(|p| glob::matches(p, &f.path)));
+
+    if excluded.is_empty() {
+        return diff.to_string();
+    }
+
+    let mut names: Vec<String> = excluded
+        .iter()
+        .take(MAX_EXCLUDED_NAMES)
+        .map(|f| format!("{} (+{} -{} lines)", f.path, f.added(), f.removed()))
+        .collect();
+    // A vendored update can touch thousands of files
+    if let Some(rest) = excluded.get(MAX_EXCLUDED_NAMES..) {
+        let added: usize = rest.iter().map(|f| f.added()).sum();
+        let removed: usize = rest.iter().map(|f| f.removed()).sum();
+        names.push(format!(
+            "and {} more files (+{} -{} lines)",
+            rest.len(),
+            added,
+            removed
+        ));
+    }
+    let mut text =
+        format!("[git-gen: excluded from the diff: {}]\n", names.join(", "));
+    for file in &kept {
+        text.push_str(&file.full());
+    }
+    text
+}
+
+/// Shrinks `diff` to roughly `budget` bytes.
+///
+/// Files keep their headers and hunk headers, the files with the most changed
+/// lines first, as long as they fit. The rest are listed by name with their
+/// line counts. Full hunks are then added back in the same order. Elided hunks
+/// are marked in place and listed at the top. Text before the first file,
+/// like the note from [`exclude`], is always kept.
+pub fn truncate(diff: &str, budget: usize) -> String {
+    if diff.len() <= budget {
+        return diff.to_string();
+    }
+
+    let files = parse(diff);
+    let names: Vec<String> = files.iter().map(|f| f.name()).collect();
+    let summaries: Vec<String> = files.iter().map(|f| f.summary()).collect();
+    let note = |elided: &[&str]| {
+        format!(
+            "[git-gen: diff is {} bytes, over the {} byte budget; hunks were \
+             elided in: {}]\n",
+            diff.len(),
+            budget,
+            elided.join(", ")
+        )
+    };
+    // Notes from earlier steps, like the list of excluded files
+    let preamble = &diff[..diff.find("diff --git a/").unwrap_or(diff.len())];
+    let mut used = note(&[]).len()
+        + preamble.len()
+        + names.iter().map(|n| n.len()).sum::<usize>();
+    let mut keep = vec![Keep::Name; files.len()];
+
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by_key(|&i| {
+        std::cmp::Reverse(files[i].added() + files[i].removed())
+    });
+    for &i in &order {
+        // The path is listed in the note as well
+        let extra = summaries[i].len().saturating_sub(names[i].len())
+            + files[i].path.len()
+            + 2;
+        if used + extra <= budget {
+            used += extra;
+            keep[i] = Keep::Summary;
+        }
+    }
+    for &i in &order {
+        // Tiny hunks can be shorter than their summary
+        let extra = files[i].full().len().saturating_sub(summaries[i].len());
+        if keep[i] == Keep::Summary && used + extra <= budget {
+            used += extra;
+            keep[i] = Keep::Full;
+        }
+    }
+
+    let elided: Vec<&str> = files
+        .iter()
+        .zip(&keep)
+        .filter(|(f, keep)| **keep == Keep::Summary && !f.hunks.is_empty())
+        .map(|(f, _)| f.path.as_str())
+        .collect();
+
+    let mut text = note(&elided);
+    text.push_str(preamble);
+    for (i, file) in files.iter().enumerate() {
+        match keep[i] {
+            Keep::Full => text.push_str(&file.full()),
+            Keep::Summary => text.push_str(&summaries[i]),
+            Keep::Name => text.push_str(&names[i]),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/Cargo.lock b/Cargo.lock
+index 1111111..2222222 100644
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -1,3 +1,6 @@
+ [[package]]
+-name = \"a\"
++name = \"b\"
++version = \"1\"
++source = \"registry\"
++checksum = \"abc\"
+diff --git a/src/main.rs b/src/main.rs
+index 3333333..4444444 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -10,4 +10,4 @@ fn main() {
+-    let config = old_config();
+-    run_the_old_way(config, &options, &mut output, Duration::from_secs(30));
++    let config = new_config();
++    run_the_new_way(config, &options, &mut output, Duration::from_secs(30));
+";
+
+    #[test]
+    fn test_parse() {
+        let files = parse(DIFF);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "Cargo.lock");
+        assert_eq!(files[0].hunks.len(), 1);
+        assert_eq!(files[0].added(), 4);
+        assert_eq!(files[0].removed(), 1);
+        assert_eq!(files[1].path, "src/main.rs");
+        assert!(files[1].header.ends_with("+++ b/src/main.rs\n"));
+    }
+
+    #[test]
+    fn test_parse_quoted_path() {
+        let diff = format!(
+            "{}diff --git \"a/caf\\303\\251 \\\"x\\\".txt\" \"b/caf\\303\\251 \\\"x\\\".txt\"\n\
+             index 5555555..6666666 100644\n\
+             --- \"a/caf\\303\\251 \\\"x\\\".txt\"\n\
+             +++ \"b/caf\\303\\251 \\\"x\\\".txt\"\n\
+             @@ -1 +1 @@\n\
+             -old\n\
+             +new\n",
+            DIFF
+        );
+        let files = parse(&diff);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[1].added(), 2);
+        assert_eq!(files[1].removed(), 2);
+        assert_eq!(files[2].path, "café \"x\".txt");
+        assert_eq!((files[2].added(), files[2].removed()), (1, 1));
+        assert_eq!(path("a/x y.txt \"b/x y.txt\""), "x y.txt");
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let text: String = parse(DIFF).iter().map(|f| f.full()).collect();
+        assert_eq!(text, DIFF);
+    }
+
+    #[test]
+    fn test_exclude() {
+        let patterns = vec!["Cargo.lock".to_string()];
+        let text = exclude(DIFF, &patterns);
+        assert!(text.starts_with(
+            "[git-gen: excluded from the diff: Cargo.lock (+4 -1 lines)]\n\
+             diff --git a/src/main.rs b/src/main.rs\n"
+        ));
+        assert!(!text.contains("checksum"));
+    }
+
+    #[test]
+    fn test_exclude_many_files() {
+        let diff: String = (0..1000)
+            .map(|i| {
+                format!(
+                    "diff --git a/vendor/{i}.c b/vendor/{i}.c\n\
+                     --- a/vendor/{i}.c\n\
+                     +++ b/vendor/{i}.c\n\
+                     @@ -1 +1,2 @@\n\
+                     -a\n\
+                     +b\n\
+                     +c\n"
+                )
+            })
+            .collect();
+        let patterns = vec!["vendor/**".to_string()];
+        let text = exclude(&diff, &patterns);
+        assert!(text.len() < 1000);
+        assert!(text.contains(
+            "vendor/19.c (+2 -1 lines), and 980 more files (+1960 -980 lines)]"
+        ));
+    }
+
+    #[test]
+    fn test_exclude_nothing() {
+        let patterns = vec!["*.min.js".to_string()];
+        assert_eq!(exclude(DIFF, &patterns), DIFF);
+    }
+
+    #[test]
+    fn test_truncate_within_budget() {
+        assert_eq!(truncate(DIFF, DIFF.len()), DIFF);
+    }
+
+    /// Length of the output when only the names fit
+    fn least(budget: usize) -> usize {
+        // The note spells out the budget
+        truncate(DIFF, 0).len() + budget.to_string().len() - 1
+    }
+
+    /// Bytes needed to show `file` with hunk headers instead of its name
+    fn summary_cost(file: &FileDiff) -> usize {
+        file.summary().len() - file.name().len() + file.path.len() + 2
+    }
+
+    #[test]
+    fn test_truncate_keeps_biggest_file() {
+        let files = parse(DIFF);
+        let budget =
+            least(100) + summary_cost(&files[0]) + files[0].full().len()
+                - files[0].summary().len()
+                + summary_cost(&files[1]);
+        let text = truncate(DIFF, budget);
+        assert!(text.contains("hunks were elided in: src/main.rs]"));
+        assert!(text.contains("+version = \"1\""));
+        assert!(!text.contains("run_the_new_way"));
+        assert!(text.contains(
+            "@@ -10,4 +10,4 @@ fn main() {\n\
+             [git-gen: hunk elided, +2 -2 lines]\n"
+        ));
+    }
+
+    #[test]
+    fn test_truncate_summaries_only() {
+        let files = parse(DIFF);
+        let budget = least(100) + files.iter().map(summary_cost).sum::<usize>();
+        let text = truncate(DIFF, budget);
+        assert!(text.len() <= budget);
+        assert!(text.contains("elided in: Cargo.lock, src/main.rs]"));
+        assert!(text.contains("+++ b/Cargo.lock"));
+        assert!(text.contains("+++ b/src/main.rs"));
+    }
+
+    #[test]
+    fn test_truncate_names_only() {
+        let text = truncate(DIFF, 0);
+        assert!(!text.contains("@@"));
+        assert!(text.ends_with(
+            "[git-gen: Cargo.lock changed, +4 -1 lines]\n\
+             [git-gen: src/main.rs changed, +2 -2 lines]\n"
+        ));
+    }
+
+    #[test]
+    fn test_truncate_stays_within_budget() {
+        // Names and counts are the least we can send
+        for budget in (0..DIFF.len()).step_by(25) {
+            let text = truncate(DIFF, budget);
+            assert!(
+                text.len() <= budget.max(least(budget)),
+                "budget {}",
+                budget
+            );
+        }
+    }
+
+    #[test]
+    fn test_exclude_then_truncate() {
+        let patterns = vec!["Cargo.lock".to_string()];
+        let text = truncate(&exclude(DIFF, &patterns), 10);
+        assert!(text.contains(
+            "[git-gen: excluded from the diff: Cargo.lock (+4 -1 lines)]\n"
+        ));
+        assert!(
+            text.ends_with("[git-gen: src/main.rs changed, +2 -2 lines]\n")
+        );
+    }
+}
@@ -1,5 +1,18 @@
 use std::error::Error as StdError;
 use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{
+    Serialize,
+    Serializer,
+    ser::SerializeStruct,
+};
+
+/// Color codes, like those clap puts in its rendered errors
+static ANSI: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\x1b\[[0-9;]*m").expect("ANSI pattern should be valid")
+});
 
 #[derive(Debug, Default)]
 pub struct Error {
@@ -64,6 +77,14 @@ impl Error {
         self
     }
 
+    /// The main message alone, without colors, sources, notes or helps
+    pub fn plain(&self) -> String {
+        match &self.raw {
+            Some(raw) => ANSI.replace_all(raw, "").into_owned(),
+            None => self.message.clone(),
+        }
+    }
+
     pub fn message(&self) -> String {
         if let Some(raw_message) = &self.raw {
             return raw_message.clone();
@@ -99,6 +120,28 @@ impl Error {
     }
 }
 
+/// Structured form of the error for `--format json`, without colors
+impl Serialize for Error {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let mut causes: Vec<String> = Vec::new();
+        let mut current_source = self.source();
+        while let Some(source) = current_source {
+            causes.push(source.to_string());
+            current_source = source.source();
+        }
+
+        let mut state = serializer.serialize_struct("Error", 4)?;
+        state.serialize_field("message", &self.plain())?;
+        state.serialize_field("causes", &causes)?;
+        state.serialize_field("notes", &self.notes)?;
+        state.serialize_field("helps", &self.helps)?;
+        state.end()
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub trait IntoHelp {
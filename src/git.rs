@@ -1,5 +1,7 @@
+use std::io::Write;
 use std::process::Stdio;
 use std::{
+    fs,
     path::PathBuf,
     process::Command,
 };
@@ -100,3 +102,127 @@ pub fn previous_commits() -> Result<String> {
     let prev_commits = String::from_utf8_lossy(&output.stdout).to_string();
     Ok(prev_commits)
 }
+
+/// Gets the subjects of the last `count` commits via `git log --format=%s`
+pub fn subjects(count: usize) -> Result<Vec<String>> {
+    let count = format!("-{}", count);
+    let output = git(&["log", "--format=%s", &count])?;
+    Ok(output.lines().map(|s| s.to_string()).collect())
+}
+
+/// Gets the abbreviated hash and full message of every commit in `range`,
+/// newest first, via `git log --format=%h%x00%B%x00`
+pub fn messages(range: &str) -> Result<Vec<(String, String)>> {
+    let output = git(&["log", "--format=%h%x00%B%x00", range])?;
+    let fields: Vec<&str> = output.split('\0').map(|f| f.trim()).collect();
+    Ok(fields
+        .chunks_exact(2)
+        .map(|pair| (pair[0].to_string(), pair[1].to_string()))
+        .collect())
+}
+
+/// Creates a commit with the given message via `git commit -F -`
+pub fn commit(message: &str) -> Result<()> {
+    let mut child = Command::new("git")
+        .args(["commit", "-F", "-"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            error!(
+                "failed to execute 'git commit -F -'",
+                source: e,
+                help: "ensure that 'git' is installed and in your system's PATH"
+            )
+        })?;
+
+    let mut stdin = child.stdin.take().expect("stdin should be piped");
+    stdin.write_all(message.as_bytes()).map_err(|e| {
+        error!("failed to write the commit message to 'git commit'", source: e)
+    })?;
+    drop(stdin);
+
+    let status = child.wait().map_err(
+        |e| error!("failed to wait for 'git commit -F -'", source: e),
+    )?;
+    if !status.success() {
+        bail!("'git commit -F -' command failed with {}", status);
+    }
+
+    Ok(())
+}
+
+/// Opens `text` in the editor configured for git and returns the result
+///
+/// The editor is resolved with `git var GIT_EDITOR`, so `core.editor`,
+/// `$VISUAL` and `$EDITOR` are honored the same way `git commit` does.
+pub fn edit(text: &str) -> Result<String> {
+    let editor = git(&["var", "GIT_EDITOR"])?;
+    let path = git_path("GITGEN_EDITMSG")?;
+
+    fs::write(&path, text).map_err(
+        |e| error!("failed to write '{}'", path.display(), source: e),
+    )?;
+
+    // Like git, let the shell handle editors with arguments
+    let status = Command::new("sh")
+        .args(["-c", &format!("{} \"$@\"", editor), "sh"])
+        .arg(&path)
+        .status()
+        .map_err(
+            |e| error!("failed to launch editor '{}'", editor, source: e),
+        )?;
+    if !status.success() {
+        bail!(
+            "editor '{}' exited with {}", editor, status,
+            help: "set your editor with 'git config core.editor' or $EDITOR"
+        );
+    }
+
+    let edited = fs::read_to_string(&path).map_err(
+        |e| error!("failed to read '{}'", path.display(), source: e),
+    )?;
+    let _ = fs::remove_file(&path);
+
+    if edited.trim().is_empty() {
+        bail!("aborting commit due to empty commit message");
+    }
+
+    Ok(edited)
+}
+
+/// Gets the current branch via `git branch --show-current`, empty when the
+/// HEAD is detached
+pub fn branch() -> Result<String> {
+    git(&["branch", "--show-current"])
+}
+
+/// Gets the configured author name via `git config user.name`
+pub fn author() -> Result<String> {
+    git(&["config", "user.name"])
+}
+
+/// Resolves a path inside the git directory via `git rev-parse --git-path`
+///
+/// Unlike joining onto `.git`, this follows worktrees and `core.hooksPath`.
+pub fn git_path(name: &str) -> Result<PathBuf> {
+    git(&["rev-parse", "--git-path", name]).map(PathBuf::from)
+}
+
+/// Runs a git command and returns its trimmed stdout
+fn git(args: &[&str]) -> Result<String> {
+    let command = format!("git {}", args.join(" "));
+    let output = Command::new("git").args(args).output().map_err(|e| {
+        error!(
+            "failed to execute '{}'", command,
+            source: e,
+            help: "ensure that 'git' is installed and in your system's PATH"
+        )
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("'{}' command failed: {}", command, stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
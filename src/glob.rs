@@ -0,0 +1,75 @@
+/// Matches `path` against a gitignore-like glob `pattern`.
+///
+/// `*` and `?` never cross a `/`, `**` does. A pattern without a `/` is
+/// matched against the file name only, so `*.min.js` excludes minified files
+/// in any directory.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return match_bytes(pattern.as_bytes(), name.as_bytes());
+    }
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    match_bytes(pattern.as_bytes(), path.as_bytes())
+}
+
+fn match_bytes(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => match rest {
+            // `**/` matches zero or more whole directories
+            [b'/', rest @ ..] => (0..=path.len())
+                .filter(|&i| i == 0 || path[i - 1] == b'/')
+                .any(|i| match_bytes(rest, &path[i..])),
+            _ => (0..=path.len()).any(|i| match_bytes(rest, &path[i..])),
+        },
+        [b'*', rest @ ..] => {
+            let end =
+                path.iter().position(|&c| c == b'/').unwrap_or(path.len());
+            (0..=end).any(|i| match_bytes(rest, &path[i..]))
+        }
+        [b'?', rest @ ..] => match path {
+            [c, path @ ..] if *c != b'/' => match_bytes(rest, path),
+            _ => false,
+        },
+        [c, rest @ ..] => match path {
+            [p, path @ ..] if p == c => match_bytes(rest, path),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert!(matches("Cargo.lock", "Cargo.lock"));
+        assert!(matches("Cargo.lock", "crates/foo/Cargo.lock"));
+        assert!(!matches("Cargo.lock", "Cargo.toml"));
+    }
+
+    #[test]
+    fn test_star() {
+        assert!(matches("*.min.js", "static/app.min.js"));
+        assert!(!matches("*.min.js", "static/app.js"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/providers/gemini.rs"));
+    }
+
+    #[test]
+    fn test_double_star() {
+        assert!(matches("vendor/**", "vendor/a/b.go"));
+        assert!(!matches("vendor/**", "src/vendor/a.go"));
+        assert!(matches("**/vendor/**", "src/vendor/a.go"));
+        assert!(matches("**/generated/*.rs", "generated/a.rs"));
+        assert!(!matches("**/generated/*.rs", "src/notgenerated/a.rs"));
+    }
+
+    #[test]
+    fn test_question_mark() {
+        assert!(matches("v?.txt", "v1.txt"));
+        assert!(!matches("v?.txt", "v10.txt"));
+        assert!(!matches("a?b", "a/b"));
+    }
+}
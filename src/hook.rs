@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+
+use crate::commit::CommitMessage;
+use crate::error::Result;
+use crate::git;
+use crate::{
+    bail,
+    error,
+};
+
+const HOOK: &str = "\
+#!/bin/sh
+# Installed by git-gen: fills in the commit message on 'git commit'
+exec git gen hook run \"$@\"
+";
+
+/// Installs the prepare-commit-msg hook into the current repository.
+pub fn install(force: bool) -> Result<()> {
+    let path = git::git_path("hooks/prepare-commit-msg")?;
+
+    if path.exists() && !force {
+        bail!(
+            "hook '{}' already exists", path.display(),
+            help: "use '--force' to overwrite it"
+        );
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(
+            |e| error!("failed to create '{}'", dir.display(), source: e),
+        )?;
+    }
+    fs::write(&path, HOOK).map_err(
+        |e| error!("failed to write '{}'", path.display(), source: e),
+    )?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = fs::Permissions::from_mode(0o755);
+        fs::set_permissions(&path, permissions).map_err(|e| {
+            error!(
+                "failed to make '{}' executable", path.display(),
+                source: e
+            )
+        })?;
+    }
+
+    println!("Installed hook at '{}'", path.display());
+    Ok(())
+}
+
+/// Whether the hook should leave the message alone.
+///
+/// We fill in the message for a plain `git commit` and for one that starts
+/// from a template, where the suggestion goes above the template text. The
+/// other sources (`-m`/`-F`, merge, squash or `--amend`/`-c`) already bring
+/// their own message.
+pub fn should_skip(source: Option<&str>) -> bool {
+    source.is_some_and(|s| !s.is_empty() && s != "template")
+}
+
+/// Writes the top suggestion into the commit message file and the rest below
+/// it as comments.
+pub fn prepare(file: &Path, commits: &[CommitMessage]) -> Result<()> {
+    let Some((first, others)) = commits.split_first() else {
+        return Ok(());
+    };
+
+    let existing = fs::read_to_string(file).map_err(
+        |e| error!("failed to read '{}'", file.display(), source: e),
+    )?;
+
+    let mut lines = vec![first.to_string(), String::new()];
+    if !others.is_empty() {
+        lines.push("# Other suggestions from git-gen:".to_string());
+        for (i, commit) in others.iter().enumerate() {
+            lines.push("#".to_string());
+            for (j, line) in commit.to_string().lines().enumerate() {
+                lines.push(match j {
+                    0 => format!("# {}. {}", i + 2, line),
+                    _ if line.is_empty() => "#".to_string(),
+                    _ => format!("#    {}", line),
+                });
+            }
+        }
+        lines.push(String::new());
+    }
+    lines.push(existing);
+
+    fs::write(file, lines.join("\n"))
+        .map_err(|e| error!("failed to write '{}'", file.display(), source: e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_skip() {
+        assert!(!should_skip(None));
+        assert!(!should_skip(Some("")));
+        assert!(!should_skip(Some("template")));
+        for source in ["message", "merge", "squash", "commit"] {
+            assert!(should_skip(Some(source)));
+        }
+    }
+
+    #[test]
+    fn test_prepare() {
+        let file = std::env::temp_dir().join("git-gen-test-prepare");
+        fs::write(&file, "# Please enter the commit message\n").unwrap();
+        let commits = vec![
+            CommitMessage::parse("feat: one\n\nbody"),
+            CommitMessage::parse("feat: two\n\nmore body"),
+        ];
+
+        prepare(&file, &commits).unwrap();
+        let content = fs::read_to_string(&file).unwrap();
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(
+            content,
+            "feat: one\n\nbody\n\n\
+             # Other suggestions from git-gen:\n\
+             #\n\
+             # 2. feat: two\n\
+             #\n\
+             #    more body\n\
+             \n\
+             # Please enter the commit message\n"
+        );
+    }
+
+    #[test]
+    fn test_prepare_template() {
+        let file = std::env::temp_dir().join("git-gen-test-prepare-template");
+        fs::write(&file, "Refs: #\n\n# Please enter the commit message\n")
+            .unwrap();
+        let commits = vec![CommitMessage::parse("fix: one")];
+
+        prepare(&file, &commits).unwrap();
+        let content = fs::read_to_string(&file).unwrap();
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(
+            content,
+            "fix: one\n\nRefs: #\n\n# Please enter the commit message\n"
+        );
+    }
+}
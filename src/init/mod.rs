@@ -0,0 +1,270 @@
+use std::fs;
+
+use crate::commit::CommitMessage;
+use crate::config::Provider;
+use crate::error::Result;
+use crate::providers::{
+    anthropic,
+    gemini,
+    grok,
+    ollama,
+    openai,
+};
+use crate::{
+    bail,
+    error,
+    git,
+    manifest,
+};
+
+/// Number of commits looked at to infer the style guide
+const HISTORY: usize = 200;
+
+/// Writes a starter GITGEN.md at the repository root.
+pub fn run(provider: &str, from_history: bool, force: bool) -> Result<()> {
+    let provider: Provider = toml::Value::String(provider.to_string())
+        .try_into()
+        .map_err(|_| {
+            error!(
+                "unknown provider: {}", provider,
+                help: "use one of: anthropic, command, gemini, grok, ollama, openai, openai-compatible"
+            )
+        })?;
+
+    let path = git::root()?.join(manifest::FILE_NAME);
+    if path.exists() && !force {
+        bail!(
+            "'{}' already exists", path.display(),
+            help: "use '--force' to overwrite it"
+        );
+    }
+
+    let prompt = match from_history {
+        true => {
+            let subjects = git::subjects(HISTORY).unwrap_or_default();
+            match infer(&subjects) {
+                Some(guide) => guide,
+                None => bail!(
+                    "no commit history to infer a style guide from",
+                    help: "run 'git gen init' without '--from-history' to use the Conventional Commits template"
+                ),
+            }
+        }
+        false => include_str!("CONVENTIONAL.md").to_string(),
+    };
+
+    let content = format!("---\n{}---\n\n{}", frontmatter(&provider), prompt);
+    fs::write(&path, content).map_err(
+        |e| error!("failed to write '{}'", path.display(), source: e),
+    )?;
+
+    println!("Created '{}'", path.display());
+    Ok(())
+}
+
+/// TOML frontmatter for `provider` with the keys it needs
+fn frontmatter(provider: &Provider) -> String {
+    let name = toml::Value::try_from(provider)
+        .expect("provider should serialize to a string");
+    let mut lines = vec![format!("provider = {}", name)];
+
+    let model_and_key = |model: &str, key: &str| {
+        vec![
+            format!("# model = \"{}\"", model),
+            format!("# api_key_env = \"{}\"", key),
+        ]
+    };
+    lines.extend(match provider {
+        Provider::Anthropic => {
+            model_and_key(anthropic::DEFAULT_MODEL, anthropic::API_KEY_ENV)
+        }
+        Provider::Gemini => {
+            model_and_key(gemini::DEFAULT_MODEL, gemini::API_KEY_ENV)
+        }
+        Provider::Grok => model_and_key(grok::DEFAULT_MODEL, grok::API_KEY_ENV),
+        Provider::OpenAI => {
+            model_and_key(openai::DEFAULT_MODEL, openai::API_KEY_ENV)
+        }
+        Provider::OpenAICompatible => vec![
+            "base_url = \"http://localhost:8000/v1\"".to_string(),
+            "model = \"your-model\"".to_string(),
+            "# api_key_env = \"MY_API_KEY\"".to_string(),
+        ],
+        Provider::Ollama => vec![
+            format!("# model = \"{}\"", ollama::DEFAULT_MODEL),
+            "# host = \"http://localhost:11434\"".to_string(),
+        ],
+        Provider::Command => {
+            vec!["command = \"llm\"".to_string(), "args = []".to_string()]
+        }
+    });
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Writes a style guide that matches the commit `subjects`, newest first.
+///
+/// Returns `None` when there is no history to learn from.
+fn infer(subjects: &[String]) -> Option<String> {
+    if subjects.is_empty() {
+        return None;
+    }
+
+    let mut types: Vec<(String, usize)> = Vec::new();
+    let mut scoped = 0;
+    let mut descriptions: Vec<String> = Vec::new();
+    for subject in subjects {
+        let header = CommitMessage::from_header(subject);
+        if let Some(kind) = header.kind {
+            match types.iter_mut().find(|(t, _)| *t == kind) {
+                Some((_, count)) => *count += 1,
+                None => types.push((kind, 1)),
+            }
+            if header.scope.is_some() {
+                scoped += 1;
+            }
+        }
+        descriptions.push(header.subject);
+    }
+    // Stable sort keeps the most recent first among equals
+    types.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    let total = subjects.len();
+    let prefixed: usize = types.iter().map(|(_, count)| count).sum();
+    let ratio = |n: usize, of: usize| n as f64 / of.max(1) as f64;
+
+    let mut rules: Vec<String> = Vec::new();
+    if ratio(prefixed, total) >= 0.5 {
+        let names: Vec<String> =
+            types.iter().map(|(t, _)| format!("`{}`", t)).collect();
+        rules.push(
+            "Start the subject with a prefix like `type: subject`.".to_string(),
+        );
+        rules.push(format!(
+            "Use one of the types used in this repository, most common first: {}.",
+            names.join(", ")
+        ));
+        match ratio(scoped, prefixed) >= 0.5 {
+            true => rules.push(
+                "Add a scope after the type, like `type(scope): subject`."
+                    .to_string(),
+            ),
+            false => rules.push(
+                "Do not add a scope after the type, it is rarely used here."
+                    .to_string(),
+            ),
+        }
+    } else {
+        rules.push(
+            "Do not start the subject with a type prefix like `feat:`."
+                .to_string(),
+        );
+    }
+
+    let mut lengths: Vec<usize> =
+        subjects.iter().map(|s| s.chars().count()).collect();
+    lengths.sort_unstable();
+    let typical = lengths[lengths.len() / 2];
+    let longest = lengths[(lengths.len() * 9 / 10).min(lengths.len() - 1)];
+    rules.push(format!(
+        "Keep the subject at {} characters or less, most are around {}.",
+        longest, typical
+    ));
+
+    let capitalized = descriptions
+        .iter()
+        .filter(|d| d.chars().next().is_some_and(|c| c.is_uppercase()))
+        .count();
+    match ratio(capitalized, descriptions.len()) >= 0.5 {
+        true => rules.push(
+            "Start the subject description with a capital letter.".to_string(),
+        ),
+        false => rules.push(
+            "Start the subject description with a lowercase letter."
+                .to_string(),
+        ),
+    }
+
+    let periods = subjects.iter().filter(|s| s.ends_with('.')).count();
+    match ratio(periods, total) >= 0.5 {
+        true => rules.push("End the subject with a period.".to_string()),
+        false => {
+            rules.push("Do not end the subject with a period.".to_string())
+        }
+    }
+
+    let mut guide = vec![
+        "Your generated commit messages MUST follow the style of this"
+            .to_string(),
+        "repository's history, described below.".to_string(),
+        String::new(),
+        "## Rules".to_string(),
+        String::new(),
+    ];
+    guide.extend(rules.iter().map(|rule| format!("- {}", rule)));
+    guide.push(String::new());
+    guide.push("## Recent Subjects".to_string());
+    guide.push(String::new());
+    guide.extend(subjects.iter().take(5).map(|s| format!("- {}", s)));
+    guide.push(String::new());
+
+    Some(guide.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subjects(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_infer_empty_history() {
+        assert_eq!(infer(&[]), None);
+    }
+
+    #[test]
+    fn test_infer_conventional() {
+        let guide = infer(&subjects(&[
+            "feat: add context and git modules",
+            "chore: add commitgen",
+            "feat: use enum for the provider",
+            "fix: improves error message",
+        ]))
+        .unwrap();
+        assert!(guide.contains("most common first: `feat`, `chore`, `fix`."));
+        assert!(guide.contains("Do not add a scope"));
+        assert!(guide.contains("with a lowercase letter"));
+        assert!(guide.contains("Do not end the subject with a period."));
+        assert!(guide.contains("- chore: add commitgen\n"));
+    }
+
+    #[test]
+    fn test_infer_plain() {
+        let guide =
+            infer(&subjects(&["Fix the build on Windows.", "Add a man page."]))
+                .unwrap();
+        assert!(guide.contains("Do not start the subject with a type prefix"));
+        assert!(guide.contains("with a capital letter"));
+        assert!(guide.contains("End the subject with a period."));
+    }
+
+    #[test]
+    fn test_frontmatter_is_valid_manifest() {
+        let providers = [
+            Provider::Anthropic,
+            Provider::Command,
+            Provider::Gemini,
+            Provider::Grok,
+            Provider::Ollama,
+            Provider::OpenAI,
+            Provider::OpenAICompatible,
+        ];
+        for provider in &providers {
+            let content =
+                format!("---\n{}---\nsome prompt\n", frontmatter(provider));
+            assert!(manifest::parse(&content).is_ok(), "{}", content);
+        }
+    }
+}
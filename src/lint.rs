@@ -0,0 +1,594 @@
+use std::fs;
+use std::path::Path;
+
+use crate::commit::CommitMessage;
+use crate::config::Config;
+use crate::error::{
+    Error,
+    Result,
+};
+use crate::{
+    error,
+    git,
+    manifest,
+};
+
+/// Line after which git drops the rest of the message with
+/// `--cleanup=scissors`, as in `git commit -v`
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Checks the commit message in `path`, as written by `git commit` for the
+/// commit-msg hook.
+pub fn file(path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        error!(
+            "failed to read commit message file '{}'", path.display(),
+            source: e
+        )
+    })?;
+    let message = clean(&content);
+    if is_ignored(&message) {
+        return Ok(());
+    }
+
+    let rules = Rules::new(&manifest::load()?.config);
+    let violations = rules.check_text(&message);
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut error = Error::new(format!(
+        "commit message breaks {} rule(s) from GITGEN.md",
+        violations.len()
+    ));
+    for violation in &violations {
+        error =
+            error.note(format!("{} ({})", violation.message, violation.rule));
+    }
+    Err(rules.helps(error, &violations))
+}
+
+/// Checks the message of every commit in the revision `range`
+pub fn range(range: &str) -> Result<()> {
+    let rules = Rules::new(&manifest::load()?.config);
+    let commits = git::messages(range)?;
+
+    let mut broken = 0;
+    let mut notes = Vec::new();
+    let mut all = Vec::new();
+    for (sha, message) in &commits {
+        if is_ignored(message) {
+            continue;
+        }
+        let violations = rules.check_text(message);
+        if violations.is_empty() {
+            continue;
+        }
+
+        broken += 1;
+        let subject = message.lines().next().unwrap_or_default();
+        for violation in &violations {
+            notes.push(format!(
+                "{} '{}': {} ({})",
+                sha, subject, violation.message, violation.rule
+            ));
+        }
+        all.extend(violations);
+    }
+
+    if broken == 0 {
+        println!("{} commit(s) follow the commit rules", commits.len());
+        return Ok(());
+    }
+
+    let mut error = Error::new(format!(
+        "{} of {} commit(s) in '{}' break the commit rules from GITGEN.md",
+        broken,
+        commits.len(),
+        range
+    ));
+    for note in notes {
+        error = error.note(note);
+    }
+    Err(rules.helps(error, &all))
+}
+
+/// Drops the comments git adds to the commit message file, and everything
+/// below the scissors line of `git commit -v`
+fn clean(content: &str) -> String {
+    let content = match content.find(SCISSORS) {
+        Some(end) => &content[..end],
+        None => content,
+    };
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// Whether `message` was written by git itself or is meant to be squashed
+/// away, like commitlint we don't hold those to the rules
+fn is_ignored(message: &str) -> bool {
+    ["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "]
+        .iter()
+        .any(|prefix| message.starts_with(prefix))
+}
+
+/// Commit message rules from the manifest frontmatter, unset rules are not
+/// checked
+#[derive(Debug, Default, Clone)]
+pub struct Rules {
+    pub max_subject: Option<usize>,
+    pub wrap_body: Option<usize>,
+    pub allowed_types: Option<Vec<String>>,
+    pub subject_period: Option<bool>,
+    pub require_scope: bool,
+}
+
+/// A rule a commit message breaks
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    /// Name of the frontmatter key behind the rule
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl Rules {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            max_subject: config.max_subject,
+            wrap_body: config.wrap_body,
+            allowed_types: config.allowed_types.clone(),
+            subject_period: config.subject_period,
+            require_scope: config.require_scope,
+        }
+    }
+
+    /// Fixes what can be fixed without changing the meaning of `message`:
+    /// the period at the end of the subject and body lines over `wrap_body`.
+    pub fn fix(&self, message: &CommitMessage) -> CommitMessage {
+        let mut message = message.clone();
+        let subject = message.subject.trim_end();
+        message.subject = match self.subject_period {
+            Some(true) if !subject.ends_with('.') => format!("{}.", subject),
+            Some(false) => subject.trim_end_matches('.').to_string(),
+            _ => subject.to_string(),
+        };
+        if let (Some(body), Some(width)) = (&message.body, self.wrap_body) {
+            message.body = Some(wrap(body, width));
+        }
+        message
+    }
+
+    /// Lists every rule `message` breaks
+    pub fn check(&self, message: &CommitMessage) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        if message.subject.trim().is_empty() {
+            violations.push(Violation {
+                rule: "subject",
+                message: "the subject is empty".to_string(),
+            });
+        }
+        if let Some(max) = self.max_subject {
+            let len = message.header().chars().count();
+            if len > max {
+                violations.push(Violation {
+                    rule: "max_subject",
+                    message: format!(
+                        "the subject is {} characters long, over the {} limit",
+                        len, max
+                    ),
+                });
+            }
+        }
+
+        if let Some(period) = self.subject_period
+            && message.subject.ends_with('.') != period
+        {
+            violations.push(Violation {
+                rule: "subject_period",
+                message: match period {
+                    true => "the subject does not end with a period",
+                    false => "the subject ends with a period",
+                }
+                .to_string(),
+            });
+        }
+
+        if self.allowed_types.is_some() || self.require_scope {
+            violations.extend(self.check_header(message));
+        }
+
+        if let (Some(body), Some(width)) = (&message.body, self.wrap_body) {
+            // The body starts after the header and a blank line
+            for (i, line) in body.lines().enumerate() {
+                let len = line.chars().count();
+                // A single long word, like a URL, can't be wrapped
+                if len > width && line.trim().contains(' ') {
+                    violations.push(Violation {
+                        rule: "wrap_body",
+                        message: format!(
+                            "line {} is {} characters long, over the {} limit",
+                            i + 3,
+                            len,
+                            width
+                        ),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Lists every rule a message written by hand breaks, including the
+    /// layout that [`CommitMessage::parse`] smooths over
+    fn check_text(&self, text: &str) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        if text
+            .lines()
+            .nth(1)
+            .is_some_and(|line| !line.trim().is_empty())
+        {
+            violations.push(Violation {
+                rule: "subject",
+                message: "the subject must be followed by a blank line"
+                    .to_string(),
+            });
+        }
+        violations.extend(self.check(&CommitMessage::parse(text)));
+        violations
+    }
+
+    /// Checks the `type(scope): ` prefix of the header
+    fn check_header(&self, message: &CommitMessage) -> Vec<Violation> {
+        let Some(kind) = &message.kind else {
+            return vec![Violation {
+                rule: "allowed_types",
+                message: "the subject does not start with `type(scope): `"
+                    .to_string(),
+            }];
+        };
+
+        let mut violations = Vec::new();
+        if let Some(types) = &self.allowed_types
+            && !types.contains(kind)
+        {
+            violations.push(Violation {
+                rule: "allowed_types",
+                message: format!(
+                    "type `{}` is not one of: {}",
+                    kind,
+                    types.join(", ")
+                ),
+            });
+        }
+        if self.require_scope
+            && message.scope.as_deref().is_none_or(|s| s.trim().is_empty())
+        {
+            violations.push(Violation {
+                rule: "require_scope",
+                message: "the subject has no `(scope)` after the type"
+                    .to_string(),
+            });
+        }
+        violations
+    }
+
+    /// How to fix a message that breaks `rule`
+    fn help(&self, rule: &str) -> String {
+        match rule {
+            "max_subject" => format!(
+                "shorten the subject to {} characters or less",
+                self.max_subject.unwrap_or_default()
+            ),
+            "wrap_body" => format!(
+                "wrap the body at {} characters",
+                self.wrap_body.unwrap_or_default()
+            ),
+            "allowed_types" => format!(
+                "start the subject with one of the types: {}",
+                self.allowed_types.as_deref().unwrap_or_default().join(", ")
+            ),
+            "subject_period" => match self.subject_period {
+                Some(true) => "end the subject with a period".to_string(),
+                _ => "remove the period at the end of the subject".to_string(),
+            },
+            "require_scope" => {
+                "add a scope after the type, like `fix(parser): ...`"
+                    .to_string()
+            }
+            _ => "write a one line subject, then a blank line before the body"
+                .to_string(),
+        }
+    }
+
+    /// Adds one help to `error` for each rule broken in `violations`
+    fn helps(&self, mut error: Error, violations: &[Violation]) -> Error {
+        let mut rules: Vec<&str> = violations.iter().map(|v| v.rule).collect();
+        rules.sort_unstable();
+        rules.dedup();
+        for rule in rules {
+            error = error.help(self.help(rule));
+        }
+        error
+    }
+
+    /// Fixes every suggestion and splits them into those that follow the
+    /// rules and those that don't, with what they break.
+    pub fn lint(
+        &self,
+        commits: Vec<CommitMessage>,
+    ) -> (Vec<CommitMessage>, Vec<(CommitMessage, Vec<Violation>)>) {
+        let mut passed = Vec::new();
+        let mut failed = Vec::new();
+        for commit in commits {
+            let commit = self.fix(&commit);
+            let violations = self.check(&commit);
+            match violations.is_empty() {
+                true => passed.push(commit),
+                false => failed.push((commit, violations)),
+            }
+        }
+        (passed, failed)
+    }
+}
+
+/// Rewraps the paragraphs of `body` that have lines over `width`.
+///
+/// List items keep their marker and get a hanging indent. Indented blocks,
+/// like code, are left alone.
+fn wrap(body: &str, width: usize) -> String {
+    let mut paragraphs = Vec::new();
+    for paragraph in body.split("\n\n") {
+        let too_long = paragraph.lines().any(|l| l.chars().count() > width);
+        let verbatim = paragraph.lines().any(|l| l.starts_with([' ', '\t']));
+        if !too_long || verbatim {
+            paragraphs.push(paragraph.to_string());
+            continue;
+        }
+
+        // Each list item is wrapped on its own
+        let mut items: Vec<(String, Vec<&str>)> = Vec::new();
+        for line in paragraph.lines() {
+            match list_marker(line) {
+                Some(marker) => items.push((
+                    marker.to_string(),
+                    line[marker.len()..].split_whitespace().collect(),
+                )),
+                None => match items.last_mut() {
+                    Some((_, words)) => words.extend(line.split_whitespace()),
+                    None => items.push((
+                        String::new(),
+                        line.split_whitespace().collect(),
+                    )),
+                },
+            }
+        }
+
+        let lines: Vec<String> = items
+            .iter()
+            .flat_map(|(marker, words)| fill(marker, words, width))
+            .collect();
+        paragraphs.push(lines.join("\n"));
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Greedily fills lines of at most `width` characters, the first starts with
+/// `marker` and the rest are indented to match it
+fn fill(marker: &str, words: &[&str], width: usize) -> Vec<String> {
+    let indent = " ".repeat(marker.chars().count());
+    let mut lines = Vec::new();
+    let mut line = marker.to_string();
+    let mut empty = true;
+    for word in words {
+        let len = line.chars().count() + word.chars().count() + 1;
+        if !empty && len > width {
+            lines.push(line);
+            line = indent.clone();
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+        }
+        line.push_str(word);
+        empty = false;
+    }
+    lines.push(line);
+    lines
+}
+
+/// The `- `, `* ` or `1. ` that starts a list item
+fn list_marker(line: &str) -> Option<&str> {
+    if line.starts_with("- ") || line.starts_with("* ") {
+        return Some(&line[..2]);
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    (digits > 0 && line[digits..].starts_with(". "))
+        .then(|| &line[..digits + 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_commit_message_file() {
+        let content = format!(
+            "feat: add lint\n\nbody\n# Please enter the commit message\n{}\ndiff --git a/x b/x\n",
+            SCISSORS
+        );
+        assert_eq!(clean(&content), "feat: add lint\n\nbody");
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        assert!(is_ignored("Merge branch 'main' into topic"));
+        assert!(is_ignored("fixup! feat: add lint"));
+        assert!(!is_ignored("feat: merge configs"));
+    }
+
+    #[test]
+    fn test_helps_once_per_rule() {
+        let rules = rules();
+        let violations = rules.check_text(&format!(
+            "chore: {}\n\n{}\n{}",
+            "a".repeat(50),
+            "b ".repeat(40),
+            "c ".repeat(40)
+        ));
+        assert_eq!(violations.len(), 4);
+        let error = rules.helps(Error::new("broken"), &violations);
+        let message = error.message();
+        assert_eq!(message.matches("help").count(), 3);
+        assert!(message.contains("wrap the body at 72 characters"));
+    }
+
+    fn fix(text: &str) -> String {
+        rules().fix(&CommitMessage::parse(text)).to_string()
+    }
+
+    fn check(rules: &Rules, text: &str) -> Vec<Violation> {
+        rules.check(&CommitMessage::parse(text))
+    }
+
+    fn rules() -> Rules {
+        Rules {
+            max_subject: Some(50),
+            wrap_body: Some(72),
+            allowed_types: Some(vec!["feat".to_string(), "fix".to_string()]),
+            subject_period: Some(false),
+            require_scope: false,
+        }
+    }
+
+    #[test]
+    fn test_fix_trailing_period() {
+        assert_eq!(fix("feat: add linting.\n"), "feat: add linting");
+    }
+
+    #[test]
+    fn test_period_without_rules() {
+        let commits = vec![CommitMessage::parse("Fix the build on Windows.")];
+        let (passed, failed) = Rules::default().lint(commits);
+        assert!(failed.is_empty());
+        assert_eq!(passed[0].subject, "Fix the build on Windows.");
+    }
+
+    #[test]
+    fn test_subject_period_required() {
+        let rules = Rules {
+            subject_period: Some(true),
+            ..Rules::default()
+        };
+        let fixed = rules.fix(&CommitMessage::parse("Fix the build"));
+        assert_eq!(fixed.subject, "Fix the build.");
+        let violations = check(&rules, "Fix the build");
+        assert_eq!(violations[0].rule, "subject_period");
+        assert!(check(&rules, "Fix the build.").is_empty());
+    }
+
+    #[test]
+    fn test_fix_blank_line_after_subject() {
+        assert_eq!(
+            fix("feat: add linting\nthe body"),
+            "feat: add linting\n\nthe body"
+        );
+    }
+
+    #[test]
+    fn test_fix_rewraps_body() {
+        let body = "word ".repeat(30);
+        let fixed = fix(&format!("fix: wrap\n\n{}", body));
+        let lines: Vec<&str> = fixed.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines.iter().all(|l| l.chars().count() <= 72));
+        assert_eq!(check(&rules(), &fixed), vec![]);
+    }
+
+    #[test]
+    fn test_fix_rewraps_list_items() {
+        let long = "word ".repeat(16);
+        let fixed = fix(&format!("fix: wrap\n\n- {}\n- short", long));
+        assert_eq!(
+            fixed,
+            format!(
+                "fix: wrap\n\n- {}\n  word word\n- short",
+                "word ".repeat(14).trim_end()
+            )
+        );
+    }
+
+    #[test]
+    fn test_fix_keeps_trailers_and_code() {
+        let message = format!(
+            "fix: wrap\n\n    {}\n\nSigned-off-by: {} <a@b.c>",
+            "code ".repeat(20),
+            "name ".repeat(20)
+        );
+        assert_eq!(fix(&message), message.trim_end());
+    }
+
+    #[test]
+    fn test_check_subject_too_long() {
+        let subject = format!("feat: {}", "a".repeat(50));
+        let violations = check(&rules(), &subject);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "max_subject");
+    }
+
+    #[test]
+    fn test_check_type_not_allowed() {
+        let violations = check(&rules(), "chore: bump deps");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "allowed_types");
+        assert!(violations[0].message.contains("`chore`"));
+    }
+
+    #[test]
+    fn test_check_missing_type() {
+        let violations = check(&rules(), "Bump deps");
+        assert_eq!(violations[0].rule, "allowed_types");
+    }
+
+    #[test]
+    fn test_check_require_scope() {
+        let rules = Rules {
+            require_scope: true,
+            ..Rules::default()
+        };
+        assert_eq!(check(&rules, "feat(lint): add rules"), vec![]);
+        assert_eq!(check(&rules, "feat!: add rules")[0].rule, "require_scope");
+    }
+
+    #[test]
+    fn test_check_long_url_is_fine() {
+        let message =
+            format!("fix: link\n\nhttps://example.com/{}", "a".repeat(80));
+        assert_eq!(check(&rules(), &message), vec![]);
+    }
+
+    #[test]
+    fn test_no_rules() {
+        let message = format!("whatever {}\nno blank line", "a".repeat(80));
+        assert_eq!(Rules::default().check_text(&message).len(), 1);
+        assert_eq!(
+            check(&Rules::default(), &format!("whatever {}", "a".repeat(80))),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_lint_splits_suggestions() {
+        let (passed, failed) = rules().lint(vec![
+            CommitMessage::parse("feat: add linting."),
+            CommitMessage::parse("docs: explain linting"),
+        ]);
+        assert_eq!(passed, vec![CommitMessage::parse("feat: add linting")]);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0.to_string(), "docs: explain linting");
+    }
+}
@@ -1,49 +1,289 @@
+use std::time::Instant;
+
 use clap::Parser;
 
 mod args;
+mod check;
+mod commit;
 mod config;
 mod context;
+mod diff;
 mod error;
 mod git;
+mod glob;
+mod hook;
+mod init;
+mod lint;
 mod manifest;
+mod output;
+mod picker;
 mod prompt;
 mod providers;
+mod redact;
 
 use crate::{
-    args::Args,
-    error::Result,
+    args::{
+        Args,
+        Command,
+        Format,
+        HookCommand,
+        PromptCommand,
+    },
+    commit::CommitMessage,
+    context::Context,
+    error::{
+        Error,
+        Result,
+        green,
+    },
+    lint::Rules,
+    manifest::Manifest,
+    output::Report,
+    picker::Choice,
     providers::Provider,
-    providers::gemini::Gemini,
 };
 
-fn run() -> Result<()> {
-    let args = Args::try_parse()?;
+/// Number of follow-up requests made when the provider returns fewer usable
+/// suggestions than asked for
+const TOP_UPS: usize = 2;
+
+fn run(args: Args) -> Result<()> {
+    match args.command {
+        Some(Command::Check) => check::run(),
+        Some(Command::Init {
+            provider,
+            from_history,
+            force,
+        }) => init::run(&provider, from_history, force),
+        Some(Command::Lint { file, range }) => match range {
+            Some(range) => lint::range(&range),
+            None => lint::file(&file.expect("clap requires a file or a range")),
+        },
+        Some(Command::Prompt { command }) => match command {
+            PromptCommand::Show => {
+                let manifest = manifest::load()?;
+                let count = count(args.count, &manifest)?;
+                let instructions = prompt::instructions(&manifest.config)?;
+                println!("{}", prompt::render(&instructions, count).trim_end());
+                Ok(())
+            }
+        },
+        Some(Command::Hook { command }) => match command {
+            HookCommand::Install { force } => hook::install(force),
+            HookCommand::Run {
+                file,
+                source,
+                count,
+                ..
+            } => {
+                if hook::should_skip(source.as_deref()) {
+                    return Ok(());
+                }
+                // A failing hook aborts the commit, so fall back to the usual
+                // empty message instead.
+                match suggest(None, Some(count)) {
+                    Ok(commits) => hook::prepare(&file, &commits),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        Ok(())
+                    }
+                }
+            }
+        },
+        None if args.print_prompt => {
+            let manifest = manifest::load()?;
+            let context = context::create(&manifest.config)?;
+            let count = count(args.count, &manifest)?;
+            let system =
+                prompt::render(&prompt::instructions(&manifest.config)?, count);
+            let user_prompt = prompt::variables::expand(&manifest.user_prompt)?;
+            println!(
+                "{}",
+                prompt::create(args.message, &user_prompt, &context, &system)
+            );
+            Ok(())
+        }
+        None if args.format == Format::Json => {
+            let manifest = manifest::load()?;
+            let context = context::create(&manifest.config)?;
+            let count = count(args.count, &manifest)?;
+            let provider = providers::create(&manifest.config)?;
+
+            let start = Instant::now();
+            let commits = generate(
+                provider.as_ref(),
+                &manifest,
+                args.message,
+                &context,
+                count,
+            )?;
+            output::json(&Report::new(
+                &manifest.config.provider,
+                provider.model(),
+                &commits,
+                start.elapsed(),
+                providers::retries(),
+            ));
+            Ok(())
+        }
+        None => loop {
+            let commits = suggest(args.message.clone(), args.count)?;
+
+            // Keep the plain list when piped or scripted
+            if !picker::is_interactive() {
+                println!("\nSuggested commit messages:");
+                for (i, commit) in commits.iter().enumerate() {
+                    println!("{}. {}", i + 1, commit);
+                }
+                return Ok(());
+            }
+
+            match picker::pick(&commits)? {
+                Choice::Commit(message) => return git::commit(&message),
+                Choice::Edit(message) => {
+                    return git::commit(&git::edit(&message)?);
+                }
+                Choice::Regenerate => continue,
+                Choice::Quit => return Ok(()),
+            }
+        },
+    }
+}
+
+/// Asks the configured provider for commit messages for the staged changes
+fn suggest(
+    draft: Option<String>,
+    count: Option<u8>,
+) -> Result<Vec<CommitMessage>> {
     let manifest = manifest::load()?;
-    let context = context::create()?;
-    let final_prompt = prompt::create(
-        args.message, //
-        &manifest.user_prompt,
-        &context,
-    );
-
-    let config = &manifest.config;
-    let provider = match &config.provider {
-        config::Provider::Gemini => Gemini::new(config.model.clone()),
-        _ => bail!("provider not implemented yet"),
+    let context = context::create(&manifest.config)?;
+    let count = self::count(count, &manifest)?;
+    let provider = providers::create(&manifest.config)?;
+    generate(provider.as_ref(), &manifest, draft, &context, count)
+}
+
+/// Number of suggestions to ask for, the CLI flag wins over the manifest
+fn count(flag: Option<u8>, manifest: &Manifest) -> Result<usize> {
+    let count = match flag {
+        Some(count) => count as usize,
+        None => manifest.config.count.unwrap_or(config::DEFAULT_COUNT),
     };
-    let commits = provider.generate(&final_prompt)?;
+    if count == 0 {
+        bail!(
+            "'count' must be at least 1",
+            help: "set 'count' in the GITGEN.md frontmatter to 1 or more"
+        );
+    }
+    Ok(count)
+}
+
+/// Asks `provider` for `count` commit messages and keeps those that follow
+/// the commit rules, asking for more when too few are left
+fn generate(
+    provider: &dyn Provider,
+    manifest: &Manifest,
+    draft: Option<String>,
+    context: &Context,
+    count: usize,
+) -> Result<Vec<CommitMessage>> {
+    let rules = Rules::new(&manifest.config);
+    let mut commits: Vec<CommitMessage> = Vec::new();
+    let mut failed = Vec::new();
+    let instructions = prompt::instructions(&manifest.config)?;
+    let user_prompt = prompt::variables::expand(&manifest.user_prompt)?;
+
+    for _ in 0..=TOP_UPS {
+        let system = prompt::render(&instructions, count - commits.len());
+        let prompt =
+            prompt::create(draft.clone(), &user_prompt, context, &system);
+        let (passed, broken) = rules.lint(provider.generate(&prompt)?);
+        for commit in passed {
+            if !commits.contains(&commit) {
+                commits.push(commit);
+            }
+        }
+        failed.extend(broken);
+        if commits.len() >= count {
+            break;
+        }
+    }
+    commits.truncate(count);
+
+    if commits.is_empty() {
+        if failed.is_empty() {
+            bail!("the provider returned no suggestions");
+        }
+        let mut error =
+            Error::new("none of the suggestions follow the commit rules");
+        for (commit, violations) in &failed {
+            for violation in violations {
+                error = error.note(format!(
+                    "'{}': {} ({})",
+                    commit.header(),
+                    violation.message,
+                    violation.rule
+                ));
+            }
+        }
+        return Err(error.help(
+            "run again, or relax the rules in the GITGEN.md frontmatter",
+        ));
+    }
 
-    println!("\nSuggested commit messages:");
-    for (i, commit) in commits.iter().enumerate() {
-        println!("{}. {}", i + 1, commit);
+    if !failed.is_empty() {
+        eprintln!(
+            "{}: dropped {} suggestion(s) that broke the commit rules",
+            green("note"),
+            failed.len()
+        );
+    }
+    if commits.len() < count {
+        eprintln!(
+            "{}: got {} of the {} suggestions asked for",
+            green("note"),
+            commits.len(),
+            count
+        );
     }
+    Ok(commits)
+}
 
-    Ok(())
+/// Whether `--format json` is in the raw arguments, so errors parsing the
+/// rest of them can be reported as JSON too
+fn wants_json() -> bool {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .collect();
+    args.iter().any(|arg| arg == "--format=json")
+        || args
+            .windows(2)
+            .any(|pair| pair[0] == "--format" && pair[1] == "json")
 }
 
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("{}", e);
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            use clap::error::ErrorKind;
+            let shown = matches!(
+                e.kind(),
+                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
+            );
+            match wants_json() && !shown {
+                true => output::json(&Error::from(e)),
+                false => eprintln!("{}", Error::from(e)),
+            }
+            std::process::exit(1);
+        }
+    };
+
+    let format = args.format;
+    if let Err(e) = run(args) {
+        match format {
+            Format::Json => output::json(&e),
+            Format::Text => eprintln!("{}", e),
+        }
         std::process::exit(1);
     }
 }
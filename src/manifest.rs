@@ -1,11 +1,20 @@
 use std::fs;
 
+use toml::{
+    Table,
+    Value,
+};
+
 use crate::bail;
+use crate::config;
 use crate::config::Config;
 use crate::error;
 use crate::error::Result;
 use crate::git;
 
+/// Name of the manifest at the repository root
+pub const FILE_NAME: &str = "GITGEN.md";
+
 /// Represents the entire parsed content of the GITGEN.md manifest file.
 #[derive(Debug)]
 pub struct Manifest {
@@ -14,17 +23,28 @@ pub struct Manifest {
 }
 
 /// Loads and parses the GITGEN.md manifest from the repository root.
+///
+/// Keys missing from its frontmatter fall back to the user config, which is
+/// used alone when the repository has no GITGEN.md.
 pub fn load() -> Result<Manifest> {
+    let user = config::load_user()?;
     let repo_root = git::root()?;
-    let manifest_path = repo_root.join("GITGEN.md");
+    let manifest_path = repo_root.join(FILE_NAME);
 
     if !manifest_path.exists() {
+        if let Some(user) = user {
+            return from_user(user);
+        }
         bail!(
             "manifest file 'GITGEN.md' not found at repository root",
             help: format!(
                 "please create a 'GITGEN.md' file in the root of your git repository at '{}'",
                 repo_root.display()
-            )
+            ),
+            help: config::user_path().map(|path| format!(
+                "or set your defaults in '{}'",
+                path.display()
+            ))
         );
     }
 
@@ -36,7 +56,11 @@ pub fn load() -> Result<Manifest> {
         )
     })?;
 
-    parse(&content).map_err(|e| {
+    let manifest = match user {
+        Some(user) => parse_with(&content, user),
+        None => parse(&content),
+    };
+    manifest.map_err(|e| {
         e.note(format!(
             "the error occurred in file '{}'",
             manifest_path.display()
@@ -46,18 +70,77 @@ pub fn load() -> Result<Manifest> {
 
 /// Parses the content of a GITGEN.md file.
 pub fn parse(content: &str) -> Result<Manifest> {
-    if !content.starts_with("---") {
-        bail!("manifest must start with TOML frontmatter delimited by '---'");
-    }
-    let parts: Vec<&str> = content.splitn(3, "---").collect();
-    if parts.len() < 3 {
-        bail!("manifest frontmatter is not closed with '---'");
-    }
+    parse_with(content, Table::new())
+}
+
+/// The raw sections of a GITGEN.md file
+#[derive(Debug)]
+pub struct Sections<'a> {
+    pub frontmatter: &'a str,
+    /// Byte offset of the frontmatter in the file
+    pub frontmatter_start: usize,
+    pub prompt: &'a str,
+    /// Byte offset of the prompt in the file
+    pub prompt_start: usize,
+}
+
+/// Splits the content of a GITGEN.md file into frontmatter and prompt.
+///
+/// The frontmatter is fenced by lines holding only `---`, or `+++` like in
+/// Hugo and Zola. A byte order mark, blank lines before the opening fence and
+/// CRLF line endings are accepted.
+pub fn split(content: &str) -> Result<Sections<'_>> {
+    let start = match content.starts_with('\u{feff}') {
+        true => '\u{feff}'.len_utf8(),
+        false => 0,
+    };
+
+    let mut lines =
+        lines(content, start).skip_while(|(_, line)| line.trim().is_empty());
+    let (fence, frontmatter_start) = match lines.next() {
+        Some((offset, line)) if matches!(line.trim_end(), "---" | "+++") => {
+            (line.trim_end(), offset + line.len())
+        }
+        _ => bail!(
+            "manifest must start with TOML frontmatter delimited by '---'",
+            help: "'+++' can be used as the delimiter too"
+        ),
+    };
 
-    let frontmatter_str = parts[1];
-    let prompt_str = parts[2];
+    let Some((end, closing)) = lines.find(|(_, line)| line.trim_end() == fence)
+    else {
+        bail!("manifest frontmatter is not closed with '{}'", fence);
+    };
+    let prompt_start = end + closing.len();
 
-    if frontmatter_str.trim().is_empty() {
+    Ok(Sections {
+        frontmatter: &content[frontmatter_start..end],
+        frontmatter_start,
+        prompt: &content[prompt_start..],
+        prompt_start,
+    })
+}
+
+/// Lines of `content` from byte `start` with their offset, line endings
+/// included
+fn lines(content: &str, start: usize) -> impl Iterator<Item = (usize, &str)> {
+    content[start..]
+        .split_inclusive('\n')
+        .scan(start, |offset, line| {
+            let item = (*offset, line);
+            *offset += line.len();
+            Some(item)
+        })
+}
+
+/// Parses the content of a GITGEN.md file on top of the `base` config, keys in
+/// the frontmatter win.
+fn parse_with(content: &str, mut base: Table) -> Result<Manifest> {
+    let sections = split(content)?;
+    let frontmatter_str = sections.frontmatter;
+    let prompt_str = sections.prompt;
+
+    if frontmatter_str.trim().is_empty() && !base.contains_key("provider") {
         bail!(
             "TOML frontmatter cannot be empty",
             help: "you must specify at least the 'provider'"
@@ -68,7 +151,19 @@ pub fn parse(content: &str) -> Result<Manifest> {
         bail!("manifest is missing the prompt after the frontmatter");
     }
 
-    let config: Config = toml::from_str(frontmatter_str).map_err(|e| {
+    let frontmatter: Table = toml::from_str(frontmatter_str).map_err(|e| {
+        error!(
+            "failed to parse TOML frontmatter in manifest",
+            source: e,
+            help: "please check for syntax errors or invalid values in your manifest config"
+        )
+    })?;
+
+    // The fallback prompt only makes sense without a GITGEN.md
+    base.remove("prompt");
+    base.extend(frontmatter);
+
+    let config: Config = Value::Table(base).try_into().map_err(|e| {
         error!(
             "failed to parse TOML frontmatter in manifest",
             source: e,
@@ -82,6 +177,35 @@ pub fn parse(content: &str) -> Result<Manifest> {
     })
 }
 
+/// Builds the manifest from the user config alone
+fn from_user(mut user: Table) -> Result<Manifest> {
+    let note = config::user_path()
+        .map(|path| format!("the error occurred in file '{}'", path.display()));
+
+    let user_prompt = match user.remove("prompt") {
+        Some(Value::String(prompt)) => prompt,
+        Some(_) => bail!(
+            "'prompt' in the user config must be a string",
+            note: note
+        ),
+        None => String::new(),
+    };
+
+    let config: Config = Value::Table(user).try_into().map_err(|e| {
+        error!(
+            "failed to parse user config",
+            source: e,
+            note: note,
+            help: "please check for invalid values in your user config"
+        )
+    })?;
+
+    Ok(Manifest {
+        config,
+        user_prompt,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,4 +263,118 @@ some prompt
                 .contains("failed to parse TOML frontmatter in manifest")
         );
     }
+
+    fn user_config() -> Table {
+        toml::from_str(
+            r#"
+provider = "openai"
+model = "gpt-4.1"
+prompt = "use emojis"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_with_user_config() {
+        let content = r#"---
+model = "gpt-4.1-mini"
+---
+some prompt
+"#;
+        let manifest = parse_with(content, user_config()).unwrap();
+        assert!(matches!(manifest.config.provider, config::Provider::OpenAI));
+        assert_eq!(manifest.config.model.as_deref(), Some("gpt-4.1-mini"));
+        assert_eq!(manifest.user_prompt, "some prompt\n");
+    }
+
+    #[test]
+    fn test_parse_empty_frontmatter_with_user_config() {
+        let content = r#"---
+---
+some prompt
+"#;
+        let manifest = parse_with(content, user_config()).unwrap();
+        assert_eq!(manifest.config.model.as_deref(), Some("gpt-4.1"));
+    }
+
+    #[test]
+    fn test_from_user() {
+        let manifest = from_user(user_config()).unwrap();
+        assert!(matches!(manifest.config.provider, config::Provider::OpenAI));
+        assert_eq!(manifest.user_prompt, "use emojis");
+    }
+
+    const FRONTMATTER: &str = "provider = \"ollama\"\n";
+
+    #[test]
+    fn test_split_dashes_inside_frontmatter() {
+        let content = "---\nprovider = \"command\"\nargs = [\"--- x ---\"]\n---\nprompt\n--- and more\n";
+        let sections = split(content).unwrap();
+        assert_eq!(
+            sections.frontmatter,
+            "provider = \"command\"\nargs = [\"--- x ---\"]\n"
+        );
+        assert_eq!(sections.prompt, "prompt\n--- and more\n");
+        assert!(parse(content).is_ok());
+    }
+
+    #[test]
+    fn test_split_horizontal_rule_in_prompt() {
+        let content =
+            format!("---\n{}---\nabove\n\n---\n\nbelow\n", FRONTMATTER);
+        let sections = split(&content).unwrap();
+        assert_eq!(sections.frontmatter, FRONTMATTER);
+        assert_eq!(sections.prompt, "above\n\n---\n\nbelow\n");
+    }
+
+    #[test]
+    fn test_split_crlf() {
+        let content = "---\r\nprovider = \"ollama\"\r\n---\r\nprompt\r\n";
+        let sections = split(content).unwrap();
+        assert_eq!(sections.frontmatter, "provider = \"ollama\"\r\n");
+        assert_eq!(sections.prompt, "prompt\r\n");
+        assert!(parse(content).is_ok());
+    }
+
+    #[test]
+    fn test_split_bom_and_leading_blank_lines() {
+        let content =
+            format!("\u{feff}\n  \n---\n{}---\nprompt\n", FRONTMATTER);
+        let sections = split(&content).unwrap();
+        assert_eq!(sections.frontmatter, FRONTMATTER);
+        assert_eq!(
+            &content[sections.frontmatter_start..][..FRONTMATTER.len()],
+            FRONTMATTER
+        );
+        assert_eq!(&content[sections.prompt_start..], "prompt\n");
+    }
+
+    #[test]
+    fn test_split_plus_fences() {
+        let content = format!("+++\n{}+++\nprompt\n---\n", FRONTMATTER);
+        let sections = split(&content).unwrap();
+        assert_eq!(sections.frontmatter, FRONTMATTER);
+        assert_eq!(sections.prompt, "prompt\n---\n");
+    }
+
+    #[test]
+    fn test_split_mismatched_fences() {
+        let content = format!("+++\n{}---\nprompt\n", FRONTMATTER);
+        let error = split(&content).unwrap_err();
+        assert!(
+            error
+                .message()
+                .contains("manifest frontmatter is not closed with '+++'")
+        );
+    }
+
+    #[test]
+    fn test_split_fence_must_be_whole_line() {
+        let content = format!("---{}---\nprompt\n", FRONTMATTER);
+        let error = split(&content).unwrap_err();
+        assert!(error.message().contains(
+            "manifest must start with TOML frontmatter delimited by '---'"
+        ));
+    }
 }
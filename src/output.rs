@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::commit::CommitMessage;
+use crate::config::Provider;
+
+/// Everything `--format json` reports about a run
+#[derive(Serialize, Debug)]
+pub struct Report<'a> {
+    pub provider: &'a Provider,
+    pub model: &'a str,
+    pub suggestions: Vec<Suggestion>,
+    pub elapsed_ms: u128,
+    pub retries: u32,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Suggestion {
+    /// The whole first line
+    pub subject: String,
+    pub body: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub footers: Vec<String>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(
+        provider: &'a Provider,
+        model: &'a str,
+        commits: &[CommitMessage],
+        elapsed: Duration,
+        retries: u32,
+    ) -> Self {
+        Self {
+            provider,
+            model,
+            suggestions: commits.iter().map(Suggestion::new).collect(),
+            elapsed_ms: elapsed.as_millis(),
+            retries,
+        }
+    }
+}
+
+impl Suggestion {
+    pub fn new(commit: &CommitMessage) -> Self {
+        Self {
+            subject: commit.header(),
+            body: commit.body.clone(),
+            kind: commit.kind.clone(),
+            scope: commit.scope.clone(),
+            breaking: commit.is_breaking(),
+            footers: commit.footers.clone(),
+        }
+    }
+}
+
+/// Prints a value as a single line of JSON to stdout
+pub fn json(value: &impl Serialize) {
+    let text = serde_json::to_string(value)
+        .expect("output types should always serialize to JSON");
+    println!("{}", text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestion_subject_only() {
+        let commit = CommitMessage::parse("feat: add json output");
+        let suggestion = Suggestion::new(&commit);
+        assert_eq!(suggestion.subject, "feat: add json output");
+        assert_eq!(suggestion.kind.as_deref(), Some("feat"));
+        assert_eq!(suggestion.body, None);
+    }
+
+    #[test]
+    fn test_suggestion_with_body() {
+        let commit = CommitMessage::parse(
+            "fix!: typo\n\nit was bothering me\n\nRefs: #4\n",
+        );
+        let suggestion = Suggestion::new(&commit);
+        assert_eq!(suggestion.subject, "fix!: typo");
+        assert_eq!(suggestion.body.as_deref(), Some("it was bothering me"));
+        assert!(suggestion.breaking);
+        assert_eq!(suggestion.footers, vec!["Refs: #4"]);
+    }
+
+    #[test]
+    fn test_error_without_colors() {
+        let error =
+            crate::error::Error::raw("\x1b[1;31merror\x1b[0m: bad value");
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["message"], "error: bad value");
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::{
+    self,
+    IsTerminal,
+    Write,
+};
+
+use crossterm::{
+    cursor,
+    event::{
+        self,
+        Event,
+        KeyCode,
+        KeyEventKind,
+        KeyModifiers,
+    },
+    queue,
+    terminal,
+};
+
+use crate::commit::CommitMessage;
+use crate::error;
+use crate::error::{
+    Result,
+    green,
+};
+
+/// What the user decided to do with the suggestions.
+#[derive(Debug)]
+pub enum Choice {
+    /// Commit with the message as is
+    Commit(String),
+    /// Open the message in the editor before committing
+    Edit(String),
+    /// Ask the provider for a new set of suggestions
+    Regenerate,
+    Quit,
+}
+
+/// Whether we can read keys from and draw to a terminal, and nobody reads
+/// stdout
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && io::stderr().is_terminal()
+}
+
+/// Lets the user choose one of the suggested commit messages.
+///
+/// The picker is drawn on stderr so stdout stays clean.
+pub fn pick(commits: &[CommitMessage]) -> Result<Choice> {
+    terminal::enable_raw_mode()
+        .map_err(|e| error!("failed to enable terminal raw mode", source: e))?;
+
+    let mut stderr = io::stderr();
+    let result = select(&mut stderr, commits);
+
+    // Always give the terminal back, even if drawing failed
+    let _ = terminal::disable_raw_mode();
+    result.map_err(|e| error!("failed to draw the commit picker", source: e))
+}
+
+fn select(
+    out: &mut impl Write,
+    commits: &[CommitMessage],
+) -> io::Result<Choice> {
+    let last = commits.len().saturating_sub(1);
+    let mut selected = 0;
+    let mut drawn = 0;
+
+    loop {
+        drawn = draw(out, commits, selected, drawn)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let choice = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                selected = selected.saturating_sub(1);
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                selected = (selected + 1).min(last);
+                None
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index <= last {
+                    selected = index;
+                }
+                None
+            }
+            KeyCode::Enter if !commits.is_empty() => {
+                Some(Choice::Commit(commits[selected].to_string()))
+            }
+            KeyCode::Char('e') if !commits.is_empty() => {
+                Some(Choice::Edit(commits[selected].to_string()))
+            }
+            KeyCode::Char('r') => Some(Choice::Regenerate),
+            KeyCode::Char('c')
+                if key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                Some(Choice::Quit)
+            }
+            KeyCode::Esc | KeyCode::Char('q') => Some(Choice::Quit),
+            _ => None,
+        };
+
+        if let Some(choice) = choice {
+            clear(out, drawn)?;
+            out.flush()?;
+            return Ok(choice);
+        }
+    }
+}
+
+/// Redraws the picker over the previous `drawn` lines and returns how many
+/// lines it now takes.
+///
+/// Only the selected message shows its body, and only as much of it as fits,
+/// so the picker never grows taller than the terminal.
+fn draw(
+    out: &mut impl Write,
+    commits: &[CommitMessage],
+    selected: usize,
+    drawn: u16,
+) -> io::Result<u16> {
+    clear(out, drawn)?;
+
+    // Cut long lines so each one takes exactly one row and the next redraw
+    // knows how far to go back up. The last row stays free, writing there
+    // would scroll the screen.
+    let (width, height) = terminal::size().unwrap_or((80, 24));
+    let digits = commits.len().to_string().len();
+    let indent = " ".repeat(digits + 2);
+    // `> ` and `10. `
+    let width = (width as usize).saturating_sub(digits + 4).max(10);
+    let rows = (height as usize).saturating_sub(3).max(1);
+
+    // Scroll the list when even the subjects don't fit
+    let shown = commits.len().min(rows);
+    let first = selected
+        .saturating_sub(shown.saturating_sub(1))
+        .min(commits.len() - shown);
+    let mut spare = rows - shown;
+
+    let cut = |line: &str| -> String { line.chars().take(width).collect() };
+    let mut lines = vec!["Suggested commit messages:".to_string()];
+    for (i, commit) in commits.iter().enumerate().skip(first).take(shown) {
+        let text = commit.to_string();
+        let mut rows = text.lines();
+        let header = format!("{}. {}", i + 1, cut(rows.next().unwrap_or("")));
+        if i != selected {
+            lines.push(format!("  {}", header));
+            continue;
+        }
+        lines.push(format!("> {}", green(header)));
+        for line in rows.take(spare) {
+            lines.push(format!("  {}{}", indent, green(cut(line))));
+            spare -= 1;
+        }
+    }
+    lines.push(
+        "[↑/↓ or 1-9] select  [enter] commit  [e] edit  [r] regenerate  [q] quit"
+            .to_string(),
+    );
+
+    for line in &lines {
+        write!(out, "{}\r\n", line)?;
+    }
+    out.flush()?;
+
+    Ok(lines.len() as u16)
+}
+
+/// Moves back up over the last `drawn` lines and clears them
+fn clear(out: &mut impl Write, drawn: u16) -> io::Result<()> {
+    if drawn > 0 {
+        queue!(out, cursor::MoveToPreviousLine(drawn))?;
+    }
+    queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown))
+}
@@ -0,0 +1,206 @@
+use std::env;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::{
+    Captures,
+    Regex,
+};
+
+use crate::error::Result;
+use crate::prompt::read_in_repo;
+use crate::{
+    error,
+    git,
+};
+
+/// Environment variables the prompt can read must start with this, so a
+/// GITGEN.md from elsewhere can't send `$OPENAI_API_KEY` to the provider
+const ENV_PREFIX: &str = "GITGEN_";
+
+/// `$$`, `${NAME}`, `${NAME:-default}`, `$NAME` or a lone `$`
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\$(?:\$|\{([A-Za-z_]\w*)(:-[^}]*)?\}|([A-Za-z_]\w*))?")
+        .expect("variable pattern should be valid")
+});
+
+/// `{{include path}}`
+static INCLUDE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*include\s+([^{}]*?)\s*\}\}")
+        .expect("include pattern should be valid")
+});
+
+/// Values for the `$NAME` variables of the user prompt
+///
+/// `$BRANCH`, `$REPO_NAME` and `$AUTHOR` come from the repository, other
+/// names are read from the environment if they start with [`ENV_PREFIX`].
+/// They are only looked up when the prompt uses them.
+struct Repository<'a> {
+    root: &'a Path,
+}
+
+impl envfmt::Context for Repository<'_> {
+    fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "BRANCH" => git::branch().ok().filter(|b| !b.is_empty()),
+            "REPO_NAME" => self
+                .root
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            "AUTHOR" => git::author().ok(),
+            _ if key.starts_with(ENV_PREFIX) => env::var(key).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Expands the variables and file includes of the user prompt.
+///
+/// `$NAME`, `${NAME}` and `${NAME:-default}` are replaced first, unknown names
+/// are left as they are. Then each `{{include path}}` is replaced with the
+/// content of the file at `path`, relative to the repository root and inside
+/// it. Included files are taken as they are.
+pub fn expand(user_prompt: &str) -> Result<String> {
+    let root = git::root()?;
+    expand_with(user_prompt, &Repository { root: &root }, &root)
+}
+
+fn expand_with(
+    text: &str,
+    variables: &impl envfmt::Context,
+    root: &Path,
+) -> Result<String> {
+    // Escape what envfmt would fail on, like `$PATH_PREFIX` in a subject
+    // copied from the history
+    let escaped = REFERENCE.replace_all(text, |caps: &Captures| {
+        let whole = &caps[0];
+        let known = match (caps.get(1), caps.get(2), caps.get(3)) {
+            (Some(_), Some(_), _) => true,
+            (Some(name), None, _) | (_, _, Some(name)) => {
+                variables.get(name.as_str()).is_some()
+            }
+            _ => whole == "$$",
+        };
+        match known {
+            true => whole.to_string(),
+            false => format!("${}", whole),
+        }
+    });
+    let text = envfmt::format_with(&escaped, variables).map_err(|e| {
+        error!(
+            "failed to expand the variables in the prompt",
+            source: e,
+            help: "write '$$' for a literal '$'"
+        )
+    })?;
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in INCLUDE.captures_iter(&text) {
+        let whole = caps.get(0).expect("group 0 is the whole match");
+        expanded.push_str(&text[last..whole.start()]);
+        let content = read_in_repo(root, &caps[1], "included in the prompt")?;
+        expanded.push_str(content.trim_end());
+        last = whole.end();
+    }
+    expanded.push_str(&text[last..]);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use super::*;
+
+    fn variables() -> HashMap<&'static str, &'static str> {
+        HashMap::from([("BRANCH", "feat/PROJ-42-login"), ("AUTHOR", "Ada")])
+    }
+
+    fn expand(text: &str) -> String {
+        expand_with(text, &variables(), &std::env::temp_dir()).unwrap()
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        let text = "Branch $BRANCH by ${AUTHOR}, ticket ${TICKET:-none}.\n\
+                    Costs $$5 or $5.";
+        assert_eq!(
+            expand(text),
+            "Branch feat/PROJ-42-login by Ada, ticket none.\nCosts $5 or $5."
+        );
+    }
+
+    #[test]
+    fn test_unknown_variables_are_kept() {
+        assert_eq!(
+            expand("quote $PATH_PREFIX in ${SCRIPT}, not $ or ${ or $$"),
+            "quote $PATH_PREFIX in ${SCRIPT}, not $ or ${ or $"
+        );
+    }
+
+    #[test]
+    fn test_environment_needs_prefix() {
+        let root = std::env::temp_dir();
+        let repository = Repository { root: &root };
+        assert_eq!(
+            expand_with("$PATH and $HOME", &repository, &root).unwrap(),
+            "$PATH and $HOME"
+        );
+        assert_eq!(
+            expand_with("${GITGEN_TEST_UNSET:-none}", &repository, &root)
+                .unwrap(),
+            "none"
+        );
+    }
+
+    #[test]
+    fn test_include() {
+        let root = std::env::temp_dir().join("git-gen-test-include");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/COMMITS.md"), "Use $SCOPE as is.\n\n")
+            .unwrap();
+
+        let text = "Rules:\n{{include docs/COMMITS.md}}\nOn $BRANCH.";
+        let expanded = expand_with(text, &variables(), &root);
+        let missing = expand_with("{{ include nope.md }}", &variables(), &root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            expanded.unwrap(),
+            "Rules:\nUse $SCOPE as is.\nOn feat/PROJ-42-login."
+        );
+        assert!(
+            missing
+                .unwrap_err()
+                .message()
+                .contains("'nope.md' included in the prompt")
+        );
+    }
+
+    #[test]
+    fn test_include_outside_repository() {
+        let root = std::env::temp_dir().join("git-gen-test-include-outside");
+        fs::create_dir_all(root.join("repo")).unwrap();
+        fs::write(root.join("secret"), "secret").unwrap();
+        let repo = root.join("repo");
+
+        let absolute =
+            format!("{{{{include {}}}}}", root.join("secret").display());
+        let results = [
+            expand_with(&absolute, &variables(), &repo),
+            expand_with("{{include ../secret}}", &variables(), &repo),
+        ];
+        fs::remove_dir_all(&root).unwrap();
+
+        for result in results {
+            assert!(
+                result
+                    .unwrap_err()
+                    .message()
+                    .contains("outside the repository")
+            );
+        }
+    }
+}
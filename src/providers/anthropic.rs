@@ -0,0 +1,111 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::error;
+use crate::{
+    commit::CommitMessage,
+    error::Result,
+    prompt::Prompt,
+    providers,
+    providers::Provider,
+    providers::structured,
+};
+
+/// Model used when the manifest doesn't set one
+pub const DEFAULT_MODEL: &str = "claude-haiku-4-5";
+
+/// Environment variable holding the API key unless `api_key_env` is set
+pub const API_KEY_ENV: &str = "ANTHROPIC_API_KEY";
+
+pub struct Anthropic {
+    model: String,
+    url: String,
+    api_key_env: String,
+}
+
+impl Anthropic {
+    pub fn new(model: Option<String>, api_key_env: Option<String>) -> Self {
+        let model = model.unwrap_or(DEFAULT_MODEL.to_string());
+        let url = "https://api.anthropic.com/v1/messages".to_string();
+        let api_key_env = api_key_env.unwrap_or(API_KEY_ENV.to_string());
+        Self {
+            model,
+            url,
+            api_key_env,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    system: &'a str,
+    messages: Vec<Message<'a>>,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    content: Vec<Block>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Block {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+impl Provider for Anthropic {
+    fn generate(&self, prompt: &Prompt) -> Result<Vec<CommitMessage>> {
+        let api_key = providers::api_key(&self.api_key_env)?;
+
+        // The Messages API has a dedicated field for system instructions, so
+        // only the context goes into the user turn.
+        let payload = Request {
+            model: &self.model,
+            max_tokens: 4096,
+            system: &prompt.system,
+            messages: vec![Message {
+                role: "user",
+                content: &prompt.context,
+            }],
+        };
+
+        let mut response = providers::send(&self.model, || {
+            ureq::post(&self.url)
+                .header("x-api-key", &api_key)
+                .header("anthropic-version", "2023-06-01")
+                .send_json(&payload)
+        })?;
+
+        let data =
+            response.body_mut().read_json::<Response>().map_err(|err| {
+                error!("failed to deserialize Anthropic API response",
+                    source: err
+                )
+            })?;
+
+        let commits: Vec<CommitMessage> = data
+            .content
+            .iter()
+            .filter(|b| b.kind == "text")
+            .flat_map(|b| structured::parse(&b.text))
+            .collect();
+
+        Ok(commits)
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::Write;
+use std::process::{
+    Command as Process,
+    Stdio,
+};
+use std::thread;
+
+use crate::{
+    bail,
+    error,
+};
+use crate::{
+    commit::CommitMessage,
+    error::Result,
+    prompt::Prompt,
+    providers::Provider,
+    providers::structured,
+};
+
+/// Pipes the prompt to an external program and reads the commit messages back
+/// from its stdout.
+pub struct Command {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Command {
+    pub fn new(program: Option<String>, args: Vec<String>) -> Result<Self> {
+        let Some(program) = program else {
+            bail!(
+                "provider 'command' requires a 'command'",
+                help: "add 'command = \"llm\"' to your GITGEN.md"
+            );
+        };
+        Ok(Self { program, args })
+    }
+}
+
+impl Provider for Command {
+    fn generate(&self, prompt: &Prompt) -> Result<Vec<CommitMessage>> {
+        let mut child = Process::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                error!(
+                    "failed to execute '{}'", self.program,
+                    source: e,
+                    help: "ensure that the command is installed and in your system's PATH"
+                )
+            })?;
+
+        // Write from another thread so a program that answers before reading
+        // all of its input can't deadlock us.
+        let mut stdin = child.stdin.take().expect("stdin should be piped");
+        let input = prompt.to_string();
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let output = child.wait_with_output().map_err(
+            |e| error!("failed to wait for '{}'", self.program, source: e),
+        )?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!(
+                "'{}' command failed: {}", self.program, stderr.trim(),
+                note: format!("the command exited with {}", output.status)
+            );
+        }
+
+        // The program may exit without reading stdin, which is fine as long
+        // as it printed something.
+        let _ = writer.join();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(structured::parse(&stdout))
+    }
+
+    fn model(&self) -> &str {
+        &self.program
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let args = vec!["-c".to_string(), script.to_string()];
+        Command::new(Some("sh".to_string()), args).unwrap()
+    }
+
+    #[test]
+    fn split_output() {
+        let command = sh("printf 'feat: one\\n---\\nfix: two\\n'");
+        let result = command.generate(&Prompt::default()).unwrap();
+        assert_eq!(result[0].to_string(), "feat: one");
+        assert_eq!(result[1].to_string(), "fix: two");
+    }
+
+    #[test]
+    fn receives_prompt() {
+        let command = sh("grep -c '<commit_generation_request>'");
+        let result = command.generate(&Prompt::default()).unwrap();
+        assert_eq!(result[0].subject, "1");
+    }
+
+    #[test]
+    fn failed_command() {
+        let command = sh("echo boom >&2; exit 3");
+        let error = command.generate(&Prompt::default()).unwrap_err();
+        assert!(error.message().contains("command failed: boom"));
+    }
+
+    #[test]
+    fn missing_program() {
+        let program = Some("git-gen-does-not-exist".to_string());
+        let command = Command::new(program, vec![]).unwrap();
+        let error = command.generate(&Prompt::default()).unwrap_err();
+        assert!(error.message().contains("failed to execute"));
+    }
+}
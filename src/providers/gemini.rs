@@ -1,42 +1,59 @@
-use std::time::Duration;
-use std::{
-    env,
-    thread,
-};
-
 use serde::{
     Deserialize,
     Serialize,
 };
+use serde_json::Value;
 
+use crate::error;
 use crate::{
-    bail,
-    error,
-};
-use crate::{
+    commit::CommitMessage,
     error::Result,
+    prompt::Prompt,
+    providers,
     providers::Provider,
+    providers::structured,
 };
 
+/// Model used when the manifest doesn't set one
+pub const DEFAULT_MODEL: &str = "gemini-2.5-flash-lite";
+
+/// Environment variable holding the API key unless `api_key_env` is set
+pub const API_KEY_ENV: &str = "GEMINI_API_KEY";
+
 pub struct Gemini {
     model: String,
     url: String,
+    api_key_env: String,
 }
 
 impl Gemini {
-    pub fn new(model: Option<String>) -> Self {
-        let model = model.unwrap_or("gemini-2.5-flash-lite".to_string());
+    pub fn new(model: Option<String>, api_key_env: Option<String>) -> Self {
+        let model = model.unwrap_or(DEFAULT_MODEL.to_string());
         let url = format!(
             "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
             model
         );
-        Self { model, url }
+        let api_key_env = api_key_env.unwrap_or(API_KEY_ENV.to_string());
+        Self {
+            model,
+            url,
+            api_key_env,
+        }
     }
 }
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 struct Request {
     contents: Vec<Content>,
+    generation_config: GenerationConfig,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    response_mime_type: &'static str,
+    response_schema: Value,
 }
 
 #[derive(Serialize)]
@@ -65,83 +82,26 @@ struct ContentResponse {
 }
 
 impl Provider for Gemini {
-    fn generate(&self, prompt: &str) -> Result<Vec<String>> {
-        let api_key = env::var("GEMINI_API_KEY").map_err(|e| {
-            error!(
-                "failed to read GEMINI_API_KEY",
-                source: e,
-                help: "please make sure GEMINI_API_KEY is defined"
-            )
-        })?;
-
-        println!("PROMPT");
-        println!("{}", prompt);
-        println!("----");
+    fn generate(&self, prompt: &Prompt) -> Result<Vec<CommitMessage>> {
+        let api_key = providers::api_key(&self.api_key_env)?;
 
         let payload = Request {
             contents: vec![Content {
                 parts: vec![Part {
-                    text: prompt.to_owned(),
+                    text: prompt.to_string(),
                 }],
             }],
+            generation_config: GenerationConfig {
+                response_mime_type: "application/json",
+                response_schema: structured::gemini_schema(),
+            },
         };
 
-        // Try up to 5 times (1 initial + 5 retries)
-        const MAX_RETRIES: u32 = 5;
-        let mut attempts = 0;
-
-        let mut response = loop {
-            attempts += 1;
-
-            let result = ureq::post(&self.url)
+        let mut response = providers::send(&self.model, || {
+            ureq::post(&self.url)
                 .header("X-goog-api-key", &api_key)
-                .send_json(&payload);
-
-            match result {
-                Ok(res) => {
-                    break res;
-                }
-                // 404 is a permanent error, no point in retrying
-                Err(ureq::Error::StatusCode(404)) => {
-                    bail!(
-                        "unknown model: {}", self.model,
-                        help: "review your commitgen.toml and make sure its valid model name"
-                    );
-                }
-                // A 5xx error is a server-side issue, so we can retry
-                Err(ureq::Error::StatusCode(code))
-                    if (500..=599).contains(&code) =>
-                {
-                    if attempts >= MAX_RETRIES {
-                        bail!(
-                            "Server error after {} attempts with status code: {}",
-                            attempts,
-                            code
-                        );
-                    }
-
-                    // Calculate wait time with exponential backoff + jitter
-                    let backoff_secs = 2u64.pow(attempts);
-                    let jitter_ms = rand::random::<u16>() % 1000;
-                    let wait_time = Duration::from_secs(backoff_secs)
-                        + Duration::from_millis(jitter_ms as u64);
-
-                    eprintln!(
-                        "Server error ({}), retrying in {:?} (attempt {}/{})",
-                        code, wait_time, attempts, MAX_RETRIES
-                    );
-                    thread::sleep(wait_time);
-                }
-                // Any other error is treated as permanent
-                Err(err) => {
-                    bail!(
-                        "unexpected error when request model: {}", self.model,
-                        source: err,
-                        help: "review your commitgen.toml and make sure its valid model name"
-                    );
-                }
-            }
-        };
+                .send_json(&payload)
+        })?;
 
         let data =
             response.body_mut().read_json::<Response>().map_err(|err| {
@@ -150,31 +110,34 @@ impl Provider for Gemini {
                 )
             })?;
 
-        let commits: Vec<String> = data
+        let commits: Vec<CommitMessage> = data
             .candidates
             .iter()
             .flat_map(|c| &c.content.parts)
-            .flat_map(|p| p.text.split("\n---\n"))
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
+            .flat_map(|p| structured::parse(&p.text))
             .collect();
 
         Ok(commits)
     }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
 }
 
 #[cfg(test)]
 mod test {
 
     use super::*;
+    use crate::config;
     use crate::context::Context;
     use crate::prompt;
 
     #[test]
     fn unknown_model() {
         let model = "random".to_string();
-        let gemini = Gemini::new(Some(model));
-        let result = gemini.generate("");
+        let gemini = Gemini::new(Some(model), None);
+        let result = gemini.generate(&Prompt::default());
         let error = result.unwrap_err();
         assert!(error.message().contains("unknown model"));
     }
@@ -182,7 +145,7 @@ mod test {
     #[test]
     fn known_model() {
         let model = "gemini-2.5-flash-lite".to_string();
-        let gemini = Gemini::new(Some(model));
+        let gemini = Gemini::new(Some(model), None);
         let draft_message = Some("add more dependencies".to_owned());
         let git_diff =  r#"
             diff --git a/Cargo.toml b/Cargo.toml
@@ -218,8 +181,10 @@ mod test {
             0d54d47 chore: add github ci
         "#.to_owned();
         let context = Context { git_diff, git_log };
-        let final_prompt = prompt::create(draft_message, "", &context);
+        let system =
+            prompt::render(prompt::INSTRUCTIONS, config::DEFAULT_COUNT);
+        let final_prompt = prompt::create(draft_message, "", &context, &system);
         let result = gemini.generate(&final_prompt).unwrap();
-        assert_eq!(result.len(), 5);
+        assert_eq!(result.len(), config::DEFAULT_COUNT);
     }
 }
@@ -0,0 +1,41 @@
+use crate::commit::CommitMessage;
+use crate::error::Result;
+use crate::prompt::Prompt;
+use crate::providers::{
+    Provider,
+    openai::OpenAI,
+};
+
+/// Model used when the manifest doesn't set one
+pub const DEFAULT_MODEL: &str = "grok-3-mini";
+
+/// Environment variable holding the API key unless `api_key_env` is set
+pub const API_KEY_ENV: &str = "XAI_API_KEY";
+
+/// xAI serves Grok through an OpenAI-compatible chat completions API, so this
+/// only differs from [`OpenAI`] in its endpoint, key and default model.
+pub struct Grok {
+    client: OpenAI,
+}
+
+impl Grok {
+    pub fn new(model: Option<String>, api_key_env: Option<String>) -> Self {
+        let client = OpenAI::with_endpoint(
+            "xAI",
+            model.unwrap_or(DEFAULT_MODEL.to_string()),
+            "https://api.x.ai/v1/chat/completions".to_string(),
+            Some(api_key_env.unwrap_or(API_KEY_ENV.to_string())),
+        );
+        Self { client }
+    }
+}
+
+impl Provider for Grok {
+    fn generate(&self, prompt: &Prompt) -> Result<Vec<CommitMessage>> {
+        self.client.generate(prompt)
+    }
+
+    fn model(&self) -> &str {
+        self.client.model()
+    }
+}
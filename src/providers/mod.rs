@@ -1,8 +1,179 @@
+use std::env;
+use std::sync::atomic::{
+    AtomicU32,
+    Ordering,
+};
+use std::thread;
+use std::time::Duration;
+
+use ureq::{
+    Body,
+    http::Response,
+};
+
+use crate::commit::CommitMessage;
+use crate::config;
+use crate::config::Config;
 use crate::error::Result;
+use crate::prompt::Prompt;
+use crate::{
+    bail,
+    error,
+};
 
+pub mod anthropic;
+pub mod command;
 pub mod gemini;
+pub mod grok;
+pub mod ollama;
+pub mod openai;
+pub mod structured;
+
+use anthropic::Anthropic;
+use command::Command;
+use gemini::Gemini;
+use grok::Grok;
+use ollama::Ollama;
+use openai::OpenAI;
 
 pub trait Provider {
     /// Generates commit messages based on the given prompt and context
-    fn generate(&self, prompt: &str) -> Result<Vec<String>>;
+    fn generate(&self, prompt: &Prompt) -> Result<Vec<CommitMessage>>;
+
+    /// The model that answers the requests
+    fn model(&self) -> &str;
+}
+
+/// Number of requests retried by [`send`] during this run
+static RETRIES: AtomicU32 = AtomicU32::new(0);
+
+/// Returns how many requests have been retried so far
+pub fn retries() -> u32 {
+    RETRIES.load(Ordering::Relaxed)
+}
+
+/// Creates the provider selected in the manifest config.
+pub fn create(config: &Config) -> Result<Box<dyn Provider>> {
+    let model = config.model.clone();
+    let api_key_env = config.api_key_env.clone();
+    let provider: Box<dyn Provider> = match &config.provider {
+        config::Provider::Anthropic => {
+            Box::new(Anthropic::new(model, api_key_env))
+        }
+        config::Provider::Command => {
+            Box::new(Command::new(config.command.clone(), config.args.clone())?)
+        }
+        config::Provider::Gemini => Box::new(Gemini::new(model, api_key_env)),
+        config::Provider::Grok => Box::new(Grok::new(model, api_key_env)),
+        config::Provider::Ollama => {
+            Box::new(Ollama::new(model, config.host.clone()))
+        }
+        config::Provider::OpenAI => Box::new(OpenAI::new(model, api_key_env)),
+        config::Provider::OpenAICompatible => Box::new(OpenAI::compatible(
+            config.base_url.clone(),
+            api_key_env,
+            model,
+        )?),
+    };
+    Ok(provider)
+}
+
+/// Name of the environment variable the configured provider reads its API
+/// key from, if it needs one
+pub fn api_key_env(config: &Config) -> Option<String> {
+    let default = match &config.provider {
+        config::Provider::Anthropic => anthropic::API_KEY_ENV,
+        config::Provider::Gemini => gemini::API_KEY_ENV,
+        config::Provider::Grok => grok::API_KEY_ENV,
+        config::Provider::OpenAI => openai::API_KEY_ENV,
+        // The key is optional for self-hosted servers
+        config::Provider::OpenAICompatible => {
+            return config.api_key_env.clone();
+        }
+        config::Provider::Command | config::Provider::Ollama => return None,
+    };
+    Some(config.api_key_env.clone().unwrap_or(default.to_string()))
+}
+
+/// Reads the API key from the environment variable `name`
+pub fn api_key(name: &str) -> Result<String> {
+    env::var(name).map_err(|e| {
+        error!(
+            "failed to read {}", name,
+            source: e,
+            help: format!("please make sure {} is defined", name)
+        )
+    })
+}
+
+/// Sends a request to the model API, retrying server errors with exponential
+/// backoff.
+///
+/// The `request` closure is called once per attempt, so it must build and
+/// send a fresh request every time.
+pub fn send(
+    model: &str,
+    request: impl Fn() -> std::result::Result<Response<Body>, ureq::Error>,
+) -> Result<Response<Body>> {
+    // Try up to 5 times (1 initial + 5 retries)
+    const MAX_RETRIES: u32 = 5;
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+
+        match request() {
+            Ok(res) => {
+                return Ok(res);
+            }
+            // 404 is a permanent error, no point in retrying
+            Err(ureq::Error::StatusCode(404)) => {
+                bail!(
+                    "unknown model: {}", model,
+                    help: "review your GITGEN.md and make sure its valid model name"
+                );
+            }
+            // A 5xx error is a server-side issue and 429 means we are rate
+            // limited (or overloaded, like Anthropic's 529), so we can retry
+            Err(ureq::Error::StatusCode(code))
+                if code == 429 || (500..=599).contains(&code) =>
+            {
+                if attempts >= MAX_RETRIES {
+                    bail!(
+                        "Server error after {} attempts with status code: {}",
+                        attempts,
+                        code
+                    );
+                }
+
+                // Calculate wait time with exponential backoff + jitter
+                let backoff_secs = 2u64.pow(attempts);
+                let jitter_ms = rand::random::<u16>() % 1000;
+                let wait_time = Duration::from_secs(backoff_secs)
+                    + Duration::from_millis(jitter_ms as u64);
+
+                eprintln!(
+                    "Server error ({}), retrying in {:?} (attempt {}/{})",
+                    code, wait_time, attempts, MAX_RETRIES
+                );
+                RETRIES.fetch_add(1, Ordering::Relaxed);
+                thread::sleep(wait_time);
+            }
+            // Any other error is treated as permanent
+            Err(err) => {
+                bail!(
+                    "unexpected error when request model: {}", model,
+                    source: err,
+                    help: "review your GITGEN.md and make sure its valid model name"
+                );
+            }
+        }
+    }
+}
+
+/// Splits the raw model output into individual commit messages.
+pub fn split(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split("\n---\n")
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
 }
@@ -0,0 +1,130 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+
+use crate::{
+    bail,
+    error,
+};
+use crate::{
+    commit::CommitMessage,
+    error::Result,
+    prompt::Prompt,
+    providers::Provider,
+    providers::structured,
+};
+
+/// Model used when the manifest doesn't set one
+pub const DEFAULT_MODEL: &str = "llama3.2";
+
+/// Talks to a local Ollama daemon, so the diff never leaves the machine.
+pub struct Ollama {
+    model: String,
+    host: String,
+    url: String,
+}
+
+impl Ollama {
+    pub fn new(model: Option<String>, host: Option<String>) -> Self {
+        let model = model.unwrap_or(DEFAULT_MODEL.to_string());
+        let host = host.unwrap_or("http://localhost:11434".to_string());
+        let url = format!("{}/api/chat", host.trim_end_matches('/'));
+        Self { model, host, url }
+    }
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    model: &'a str,
+    messages: Vec<Message>,
+    stream: bool,
+    /// JSON schema the answer must follow
+    format: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    message: Message,
+}
+
+impl Provider for Ollama {
+    fn generate(&self, prompt: &Prompt) -> Result<Vec<CommitMessage>> {
+        let payload = Request {
+            model: &self.model,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+            stream: false,
+            format: structured::schema(),
+        };
+
+        // No retries here: the daemon is local, so a failure is not going to
+        // fix itself in a few seconds.
+        let result = ureq::post(&self.url).send_json(&payload);
+        let mut response = match result {
+            Ok(res) => res,
+            Err(ureq::Error::StatusCode(404)) => {
+                bail!(
+                    "unknown model: {}", self.model,
+                    help: format!("download it first with 'ollama pull {}'", self.model)
+                );
+            }
+            Err(
+                err @ (ureq::Error::Io(_)
+                | ureq::Error::ConnectionFailed
+                | ureq::Error::HostNotFound),
+            ) => {
+                bail!(
+                    "failed to connect to Ollama at '{}'", self.host,
+                    source: err,
+                    help: "make sure the Ollama daemon is running, e.g. with 'ollama serve'",
+                    help: "set 'host' in your GITGEN.md if Ollama listens on another address"
+                );
+            }
+            Err(err) => {
+                bail!(
+                    "unexpected error when request model: {}", self.model,
+                    source: err,
+                    help: "review your GITGEN.md and make sure its valid model name"
+                );
+            }
+        };
+
+        let data =
+            response.body_mut().read_json::<Response>().map_err(|err| {
+                error!("failed to deserialize Ollama API response",
+                    source: err
+                )
+            })?;
+
+        Ok(structured::parse(&data.message.content))
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unreachable_daemon() {
+        // Nothing listens on the discard port
+        let host = Some("http://127.0.0.1:9".to_string());
+        let ollama = Ollama::new(None, host);
+        let result = ollama.generate(&Prompt::default());
+        let error = result.unwrap_err();
+        assert!(error.message().contains("failed to connect to Ollama"));
+    }
+}
@@ -0,0 +1,206 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    Value,
+    json,
+};
+
+use crate::{
+    bail,
+    error,
+};
+use crate::{
+    commit::CommitMessage,
+    error::Result,
+    prompt::Prompt,
+    providers,
+    providers::Provider,
+    providers::structured,
+};
+
+/// Model used when the manifest doesn't set one
+pub const DEFAULT_MODEL: &str = "gpt-4.1-mini";
+
+/// Environment variable holding the API key unless `api_key_env` is set
+pub const API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+pub struct OpenAI {
+    name: &'static str,
+    model: String,
+    url: String,
+    api_key_env: Option<String>,
+    /// Whether the endpoint takes a JSON schema in `response_format`
+    structured: bool,
+}
+
+impl OpenAI {
+    pub fn new(model: Option<String>, api_key_env: Option<String>) -> Self {
+        Self::with_endpoint(
+            "OpenAI",
+            model.unwrap_or(DEFAULT_MODEL.to_string()),
+            "https://api.openai.com/v1/chat/completions".to_string(),
+            Some(api_key_env.unwrap_or(API_KEY_ENV.to_string())),
+        )
+    }
+
+    /// Creates a client for a self-hosted or third-party server such as
+    /// vLLM, LM Studio or LiteLLM.
+    ///
+    /// `base_url` is the API root including the version, for example
+    /// `http://localhost:8000/v1`. Without `api_key_env` no `Authorization`
+    /// header is sent. Not every server takes `response_format`, so the model
+    /// only gets the JSON contract from the instructions.
+    pub fn compatible(
+        base_url: Option<String>,
+        api_key_env: Option<String>,
+        model: Option<String>,
+    ) -> Result<Self> {
+        let Some(base_url) = base_url else {
+            bail!(
+                "provider 'openai-compatible' requires a 'base_url'",
+                help: "add 'base_url = \"http://localhost:8000/v1\"' to your GITGEN.md"
+            );
+        };
+        let Some(model) = model else {
+            bail!(
+                "provider 'openai-compatible' requires a 'model'",
+                help: "add the model name served by your endpoint to your GITGEN.md"
+            );
+        };
+        let url =
+            format!("{}/chat/completions", base_url.trim_end_matches('/'));
+        Ok(Self {
+            structured: false,
+            ..Self::with_endpoint("OpenAI-compatible", model, url, api_key_env)
+        })
+    }
+
+    /// Creates a client for any API that speaks the OpenAI chat completions
+    /// protocol.
+    pub fn with_endpoint(
+        name: &'static str,
+        model: String,
+        url: String,
+        api_key_env: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            model,
+            url,
+            api_key_env,
+            structured: true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    model: &'a str,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+impl Provider for OpenAI {
+    fn generate(&self, prompt: &Prompt) -> Result<Vec<CommitMessage>> {
+        let api_key = match &self.api_key_env {
+            Some(name) => Some(providers::api_key(name)?),
+            None => None,
+        };
+
+        let payload = Request {
+            model: &self.model,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+            response_format: self.structured.then(|| {
+                json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": "commit_messages",
+                        "strict": true,
+                        "schema": structured::schema(),
+                    }
+                })
+            }),
+        };
+
+        let mut response = providers::send(&self.model, || {
+            let mut request = ureq::post(&self.url);
+            if let Some(api_key) = &api_key {
+                request = request
+                    .header("Authorization", format!("Bearer {}", api_key));
+            }
+            request.send_json(&payload)
+        })?;
+
+        let data =
+            response.body_mut().read_json::<Response>().map_err(|err| {
+                error!("failed to deserialize {} API response", self.name,
+                    source: err
+                )
+            })?;
+
+        let commits: Vec<CommitMessage> = data
+            .choices
+            .iter()
+            .flat_map(|c| structured::parse(&c.message.content))
+            .collect();
+
+        Ok(commits)
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compatible_requires_base_url() {
+        let model = Some("llama3".to_string());
+        let result = OpenAI::compatible(None, None, model);
+        let error = result.err().unwrap();
+        assert!(error.message().contains("requires a 'base_url'"));
+    }
+
+    #[test]
+    fn compatible_requires_model() {
+        let base_url = Some("http://localhost:8000/v1".to_string());
+        let result = OpenAI::compatible(base_url, None, None);
+        let error = result.err().unwrap();
+        assert!(error.message().contains("requires a 'model'"));
+    }
+
+    #[test]
+    fn compatible_url() {
+        let base_url = Some("http://localhost:8000/v1/".to_string());
+        let model = Some("llama3".to_string());
+        let openai = OpenAI::compatible(base_url, None, model).unwrap();
+        assert_eq!(openai.url, "http://localhost:8000/v1/chat/completions");
+        assert!(openai.api_key_env.is_none());
+        assert!(!openai.structured);
+    }
+}
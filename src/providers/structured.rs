@@ -0,0 +1,244 @@
+use serde::Deserialize;
+use serde_json::{
+    Value,
+    json,
+};
+
+use crate::commit::CommitMessage;
+use crate::providers;
+
+/// One commit message as the model returns it in structured output
+#[derive(Debug, Deserialize)]
+struct Suggestion {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    scope: Option<String>,
+    subject: String,
+    body: Option<String>,
+    #[serde(default)]
+    footers: Vec<String>,
+}
+
+/// The whole answer, models sometimes drop the wrapping object
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Answer {
+    Object { suggestions: Vec<Suggestion> },
+    List(Vec<Suggestion>),
+}
+
+impl From<Suggestion> for CommitMessage {
+    fn from(suggestion: Suggestion) -> Self {
+        let trimmed = |field: Option<String>| {
+            field
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let subject = suggestion.subject.trim();
+        let kind = trimmed(suggestion.kind);
+
+        // Some models repeat the prefix in the subject, or give a `!` in the
+        // type
+        let mut message = CommitMessage::from_header(subject);
+        if message.kind.is_none() || kind.is_none() {
+            message = CommitMessage {
+                breaking: kind.as_deref().is_some_and(|k| k.ends_with('!')),
+                kind: kind.map(|k| k.trim_end_matches('!').to_string()),
+                scope: trimmed(suggestion.scope),
+                subject: subject.to_string(),
+                ..CommitMessage::default()
+            };
+        }
+        message.body = trimmed(suggestion.body);
+        message.footers = suggestion
+            .footers
+            .iter()
+            .map(|f| f.trim().to_string())
+            .filter(|f| !f.is_empty())
+            .collect();
+        message
+    }
+}
+
+/// Reads the commit messages from the model output.
+///
+/// The output should be the JSON asked for in the instructions, but models
+/// wrap it in code fences or a preamble now and then. Text that holds no JSON
+/// at all is split on `---` lines like before, and so is text that merely
+/// mentions brackets, like `fix: return [] for empty input`.
+pub fn parse(text: &str) -> Vec<CommitMessage> {
+    let start = text.find(['{', '[']);
+    let end = text.rfind(['}', ']']);
+    if let (Some(start), Some(end)) = (start, end)
+        && start < end
+        && let Ok(answer) = serde_json::from_str::<Answer>(&text[start..=end])
+    {
+        let suggestions = match answer {
+            Answer::Object { suggestions } => suggestions,
+            Answer::List(suggestions) => suggestions,
+        };
+        let messages: Vec<CommitMessage> = suggestions
+            .into_iter()
+            .map(CommitMessage::from)
+            .filter(|m| !m.subject.is_empty())
+            .collect();
+        if !messages.is_empty() || is_json(text) {
+            return messages;
+        }
+    }
+
+    providers::split(text)
+        .map(|text| CommitMessage::parse(&text))
+        .collect()
+}
+
+/// Whether `text` is JSON once an opening code fence is dropped
+fn is_json(text: &str) -> bool {
+    let text = text.trim_start();
+    let text = match text.strip_prefix("```") {
+        Some(fenced) => fenced.split_once('\n').map_or("", |(_, rest)| rest),
+        None => text,
+    };
+    text.trim_start().starts_with(['{', '['])
+}
+
+/// JSON schema of the answer, for APIs that take a standard one
+///
+/// Every field is required with `null` allowed instead, as OpenAI's strict
+/// mode asks.
+pub fn schema() -> Value {
+    let nullable = |kind: &str| json!({ "type": [kind, "null"] });
+    json!({
+        "type": "object",
+        "properties": {
+            "suggestions": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "type": nullable("string"),
+                        "scope": nullable("string"),
+                        "subject": { "type": "string" },
+                        "body": nullable("string"),
+                        "footers": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        }
+                    },
+                    "required": ["type", "scope", "subject", "body", "footers"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["suggestions"],
+        "additionalProperties": false
+    })
+}
+
+/// The same schema in the OpenAPI subset Gemini's `responseSchema` takes
+pub fn gemini_schema() -> Value {
+    let string = json!({ "type": "STRING" });
+    let nullable = json!({ "type": "STRING", "nullable": true });
+    json!({
+        "type": "OBJECT",
+        "properties": {
+            "suggestions": {
+                "type": "ARRAY",
+                "items": {
+                    "type": "OBJECT",
+                    "properties": {
+                        "type": nullable,
+                        "scope": nullable,
+                        "subject": string,
+                        "body": nullable,
+                        "footers": { "type": "ARRAY", "items": string }
+                    },
+                    "required": ["subject"],
+                    "propertyOrdering": ["type", "scope", "subject", "body", "footers"]
+                }
+            }
+        },
+        "required": ["suggestions"]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(text: &str) -> Vec<String> {
+        parse(text).iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let text = r#"{"suggestions": [
+            {"type": "feat", "scope": "lint", "subject": "add rules",
+             "body": "Check the subject.", "footers": ["Refs: #12"]},
+            {"type": "fix", "scope": null, "subject": "wrap body",
+             "body": null, "footers": []}
+        ]}"#;
+        assert_eq!(
+            texts(text),
+            vec![
+                "feat(lint): add rules\n\nCheck the subject.\n\nRefs: #12",
+                "fix: wrap body",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_code_fence_and_preamble() {
+        let text =
+            "Here you go:\n```json\n[{\"subject\": \"Update docs\"}]\n```\n";
+        assert_eq!(texts(text), vec!["Update docs"]);
+    }
+
+    #[test]
+    fn test_parse_horizontal_rule_in_body() {
+        let text = r#"{"suggestions": [{"type": "docs", "subject": "add table",
+            "body": "before\n---\nafter"}]}"#;
+        assert_eq!(texts(text), vec!["docs: add table\n\nbefore\n---\nafter"]);
+    }
+
+    #[test]
+    fn test_parse_falls_back_to_split() {
+        let text = "feat: one\n---\nfix: two (see [docs])\n";
+        assert_eq!(texts(text), vec!["feat: one", "fix: two (see [docs])"]);
+    }
+
+    #[test]
+    fn test_parse_brackets_in_plain_text() {
+        let text =
+            "fix: return [] for empty input\n---\nfix: handle empty list";
+        assert_eq!(
+            texts(text),
+            vec!["fix: return [] for empty input", "fix: handle empty list"]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_answer() {
+        assert!(parse("```json\n{\"suggestions\": []}\n```").is_empty());
+    }
+
+    #[test]
+    fn test_parse_prefix_in_subject() {
+        let text = r#"[{"type": "feat", "scope": "lint",
+            "subject": "feat(lint)!: add rules"}]"#;
+        let messages = parse(text);
+        assert_eq!(messages[0].to_string(), "feat(lint)!: add rules");
+        assert!(messages[0].breaking);
+    }
+
+    #[test]
+    fn test_parse_structure() {
+        let text = r#"[{"type": "fix", "scope": "hook", "subject": "skip merges",
+            "footers": ["BREAKING CHANGE: merges keep their message"]}]"#;
+        let message = &parse(text)[0];
+        assert_eq!(message.kind.as_deref(), Some("fix"));
+        assert_eq!(message.scope.as_deref(), Some("hook"));
+        assert_eq!(message.body, None);
+        assert!(message.is_breaking());
+    }
+}